@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::instruction::Instruction;
+use crate::symbols::SymbolMap;
+use crate::{PROGRAM_START, RAM};
+
+// Source syntax, the same one chip8-disasm --asm prints:
+//
+//     ; comment
+//     SPEED equ 4            constant
+//     main:                  label, may share its line with an instruction
+//         LD V0, SPEED
+//         LD I, sprite
+//         DRW V0, V1, 5
+//         JP main
+//     sprite:
+//         db 0xF0, 0x90, 0b11110000
+//         dw 0x1234
+//
+// Mnemonics and registers are case insensitive, numbers are decimal, 0x hex or 0b binary.
+// Operands may add or subtract numbers, constants and labels, e.g. `LD I, sprite + 5`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+enum Statement<'a> {
+    Instruction(&'a str, Vec<&'a str>),
+    Bytes(Vec<&'a str>),
+    Words(Vec<&'a str>),
+}
+
+struct Line<'a> {
+    number: usize,
+    statement: Statement<'a>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operand {
+    Register(u8),
+    Value(i64),
+    I,
+    IndirectI,
+    Delay,
+    Sound,
+    Key,
+    Font,
+    BigFont,
+    Bcd,
+    Flags,
+}
+
+fn error(line: usize, message: String) -> AsmError {
+    AsmError { line, message }
+}
+
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && register(name).is_none()
+}
+
+fn register(name: &str) -> Option<u8> {
+    let name = name.to_ascii_uppercase();
+    let digit = name.strip_prefix('V')?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+fn number(text: &str) -> Option<i64> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+fn split_operands(text: &str) -> Vec<&str> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    text.split(',').map(str::trim).collect()
+}
+
+fn instruction_size(mnemonic: &str, operands: &[&str]) -> usize {
+    let long = operands.len() == 2
+        && operands[0].eq_ignore_ascii_case("i")
+        && operands[1]
+            .split_whitespace()
+            .next()
+            .is_some_and(|word| word.eq_ignore_ascii_case("long"));
+    if mnemonic.eq_ignore_ascii_case("ld") && long {
+        4
+    } else {
+        2
+    }
+}
+
+struct Assembler<'a> {
+    symbols: HashMap<&'a str, i64>,
+}
+
+impl<'a> Assembler<'a> {
+    fn value(&self, line: usize, text: &str) -> Result<i64, AsmError> {
+        let mut total: i64 = 0;
+        let mut sign = 1;
+        let mut expect_term = true;
+        for token in tokenize(text) {
+            match token {
+                "+" | "-" if expect_term => {
+                    if token == "-" {
+                        sign = -sign;
+                    }
+                }
+                "+" | "-" => {
+                    sign = if token == "-" { -1 } else { 1 };
+                    expect_term = true;
+                }
+                _ if expect_term => {
+                    let term = match number(token) {
+                        Some(term) => term,
+                        None => match self.symbols.get(token) {
+                            Some(term) => *term,
+                            None if is_symbol(token) => {
+                                return Err(error(line, format!("unknown symbol `{}`", token)))
+                            }
+                            None => return Err(error(line, format!("bad number `{}`", token))),
+                        },
+                    };
+                    total = term
+                        .checked_mul(sign)
+                        .and_then(|term| total.checked_add(term))
+                        .ok_or_else(|| error(line, format!("`{}` overflows", text)))?;
+                    sign = 1;
+                    expect_term = false;
+                }
+                _ => return Err(error(line, format!("expected + or - before `{}`", token))),
+            }
+        }
+        if expect_term {
+            return Err(error(line, format!("incomplete expression `{}`", text)));
+        }
+        Ok(total)
+    }
+
+    fn operand(&self, line: usize, text: &str) -> Result<Operand, AsmError> {
+        if let Some(register) = register(text) {
+            return Ok(Operand::Register(register));
+        }
+        let operand = match text.to_ascii_uppercase().as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::IndirectI,
+            "DT" => Operand::Delay,
+            "ST" => Operand::Sound,
+            "K" => Operand::Key,
+            "F" => Operand::Font,
+            "HF" => Operand::BigFont,
+            "B" => Operand::Bcd,
+            "R" => Operand::Flags,
+            _ => Operand::Value(self.value(line, text)?),
+        };
+        Ok(operand)
+    }
+
+    fn instruction(
+        &self,
+        line: usize,
+        mnemonic: &str,
+        operands: &[&str],
+    ) -> Result<(Instruction, Option<u16>), AsmError> {
+        let mnemonic = mnemonic.to_ascii_uppercase();
+        // LD I, LONG addr is the only instruction with a second word
+        if mnemonic == "LD" && instruction_size(&mnemonic, operands) == 4 {
+            let target = operands[1]
+                .split_whitespace()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let target = self.value(line, &target)?;
+            if !(0..=0xFFFF).contains(&target) {
+                return Err(error(line, format!("long address {} out of range", target)));
+            }
+            return Ok((Instruction::LoadILong, Some(target as u16)));
+        }
+
+        let parsed = operands
+            .iter()
+            .map(|operand| self.operand(line, operand))
+            .collect::<Result<Vec<Operand>, AsmError>>()?;
+        let address = |value: i64| -> Result<u16, AsmError> {
+            if (0..=0xFFF).contains(&value) {
+                Ok(value as u16)
+            } else {
+                Err(error(line, format!("address {} out of range", value)))
+            }
+        };
+        let byte = |value: i64| -> Result<u8, AsmError> {
+            if (-128..=255).contains(&value) {
+                Ok(value as u8)
+            } else {
+                Err(error(line, format!("byte {} out of range", value)))
+            }
+        };
+        let nibble = |value: i64| -> Result<u8, AsmError> {
+            if (0..=15).contains(&value) {
+                Ok(value as u8)
+            } else {
+                Err(error(line, format!("nibble {} out of range", value)))
+            }
+        };
+
+        use Operand::*;
+        let instruction = match (mnemonic.as_str(), parsed.as_slice()) {
+            ("CLS", []) => Instruction::ClearScreen,
+            ("RET", []) => Instruction::Return,
+            ("SCD", [Value(n)]) => Instruction::ScrollDown(nibble(*n)?),
+            ("SCU", [Value(n)]) => Instruction::ScrollUp(nibble(*n)?),
+            ("SCR", []) => Instruction::ScrollRight,
+            ("SCL", []) => Instruction::ScrollLeft,
+            ("EXIT", []) => Instruction::Exit,
+            ("LOW", []) => Instruction::LowRes,
+            ("HIGH", []) => Instruction::HighRes,
+            ("JP", [Value(nnn)]) => Instruction::Jump(address(*nnn)?),
+            ("JP", [Register(0), Value(nnn)]) => Instruction::JumpWithOffset(address(*nnn)?),
+            ("CALL", [Value(nnn)]) => Instruction::Call(address(*nnn)?),
+            ("SE", [Register(x), Value(kk)]) => Instruction::SkipIfEqual(*x, byte(*kk)?),
+            ("SE", [Register(x), Register(y)]) => Instruction::SkipIfRegistersEqual(*x, *y),
+            ("SNE", [Register(x), Value(kk)]) => Instruction::SkipIfNotEqual(*x, byte(*kk)?),
+            ("SNE", [Register(x), Register(y)]) => Instruction::SkipIfRegistersNotEqual(*x, *y),
+            ("SAVE", [Register(x), Register(y)]) => Instruction::SaveRange(*x, *y),
+            ("LOAD", [Register(x), Register(y)]) => Instruction::LoadRange(*x, *y),
+            ("LD", [Register(x), Value(kk)]) => Instruction::Store(*x, byte(*kk)?),
+            ("LD", [Register(x), Register(y)]) => Instruction::Copy(*x, *y),
+            ("LD", [Register(x), Delay]) => Instruction::StoreDelay(*x),
+            ("LD", [Register(x), Key]) => Instruction::WaitForKey(*x),
+            ("LD", [Register(x), IndirectI]) => Instruction::LoadRegisters(*x),
+            ("LD", [Register(x), Flags]) => Instruction::LoadFlags(*x),
+            ("LD", [I, Value(nnn)]) => Instruction::LoadI(address(*nnn)?),
+            ("LD", [Delay, Register(x)]) => Instruction::SetDelay(*x),
+            ("LD", [Sound, Register(x)]) => Instruction::SetSound(*x),
+            ("LD", [Font, Register(x)]) => Instruction::LoadFont(*x),
+            ("LD", [BigFont, Register(x)]) => Instruction::LoadBigFont(*x),
+            ("LD", [Bcd, Register(x)]) => Instruction::StoreBcd(*x),
+            ("LD", [IndirectI, Register(x)]) => Instruction::SaveRegisters(*x),
+            ("LD", [Flags, Register(x)]) => Instruction::SaveFlags(*x),
+            ("ADD", [Register(x), Value(kk)]) => Instruction::Add(*x, byte(*kk)?),
+            ("ADD", [Register(x), Register(y)]) => Instruction::AddRegister(*x, *y),
+            ("ADD", [I, Register(x)]) => Instruction::AddToI(*x),
+            ("OR", [Register(x), Register(y)]) => Instruction::Or(*x, *y),
+            ("AND", [Register(x), Register(y)]) => Instruction::And(*x, *y),
+            ("XOR", [Register(x), Register(y)]) => Instruction::Xor(*x, *y),
+            ("SUB", [Register(x), Register(y)]) => Instruction::Sub(*x, *y),
+            ("SUBN", [Register(x), Register(y)]) => Instruction::SubReverse(*x, *y),
+            ("SHR", [Register(x)]) => Instruction::ShiftRight(*x, *x),
+            ("SHR", [Register(x), Register(y)]) => Instruction::ShiftRight(*x, *y),
+            ("SHL", [Register(x)]) => Instruction::ShiftLeft(*x, *x),
+            ("SHL", [Register(x), Register(y)]) => Instruction::ShiftLeft(*x, *y),
+            ("RND", [Register(x), Value(kk)]) => Instruction::Random(*x, byte(*kk)?),
+            ("DRW", [Register(x), Register(y), Value(n)]) => Instruction::Draw(*x, *y, nibble(*n)?),
+            ("SKP", [Register(x)]) => Instruction::SkipIfPressed(*x),
+            ("SKNP", [Register(x)]) => Instruction::SkipIfNotPressed(*x),
+            ("PLANE", [Value(n)]) if (0..=3).contains(n) => Instruction::SelectPlane(*n as u8),
+            ("AUDIO", []) => Instruction::LoadAudioPattern,
+            ("PITCH", [Register(x)]) => Instruction::SetPitch(*x),
+            (
+                "CLS" | "RET" | "SCD" | "SCU" | "SCR" | "SCL" | "EXIT" | "LOW" | "HIGH" | "JP"
+                | "CALL" | "SE" | "SNE" | "SAVE" | "LOAD" | "LD" | "ADD" | "OR" | "AND" | "XOR"
+                | "SUB" | "SUBN" | "SHR" | "SHL" | "RND" | "DRW" | "SKP" | "SKNP" | "PLANE"
+                | "AUDIO" | "PITCH",
+                _,
+            ) => {
+                return Err(error(
+                    line,
+                    format!("bad operands for {}: `{}`", mnemonic, operands.join(", ")),
+                ))
+            }
+            _ => return Err(error(line, format!("unknown mnemonic `{}`", mnemonic))),
+        };
+        Ok((instruction, None))
+    }
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        if c == '+' || c == '-' || c.is_whitespace() {
+            if let Some(from) = start.take() {
+                tokens.push(&text[from..index]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&text[index..index + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(from) = start {
+        tokens.push(&text[from..]);
+    }
+    tokens
+}
+
+// Assembles source into a ROM image that starts at PROGRAM_START
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    assemble_with_symbols(source).map(|(rom, _)| rom)
+}
+
+// Same as assemble, also returns the address of every label and instruction line
+pub fn assemble_with_symbols(source: &str) -> Result<(Vec<u8>, SymbolMap), AsmError> {
+    let mut symbol_map = SymbolMap::new();
+    let mut assembler = Assembler {
+        symbols: HashMap::new(),
+    };
+    let mut lines = Vec::new();
+    let mut address = PROGRAM_START;
+
+    // First pass: lay out addresses, collect labels and constants
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        let mut text = text.split(';').next().unwrap_or("").trim();
+
+        if let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if !is_symbol(label) {
+                return Err(error(number, format!("bad label name `{}`", label)));
+            }
+            if assembler.symbols.insert(label, address as i64).is_some() {
+                return Err(error(number, format!("`{}` is defined twice", label)));
+            }
+            symbol_map.add_label(label, address);
+            text = text[colon + 1..].trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match text.find(char::is_whitespace) {
+            Some(space) => (&text[..space], text[space..].trim()),
+            None => (text, ""),
+        };
+        if let Some(value) = rest
+            .split_once(char::is_whitespace)
+            .filter(|(keyword, _)| keyword.eq_ignore_ascii_case("equ"))
+            .map(|(_, value)| value.trim())
+        {
+            if !is_symbol(mnemonic) {
+                return Err(error(number, format!("bad constant name `{}`", mnemonic)));
+            }
+            let value = assembler.value(number, value)?;
+            if assembler.symbols.insert(mnemonic, value).is_some() {
+                return Err(error(number, format!("`{}` is defined twice", mnemonic)));
+            }
+            continue;
+        }
+
+        let operands = split_operands(rest);
+        let statement = match mnemonic.to_ascii_lowercase().as_str() {
+            "db" => {
+                address += operands.len();
+                Statement::Bytes(operands)
+            }
+            "dw" => {
+                address += operands.len() * 2;
+                Statement::Words(operands)
+            }
+            _ => {
+                symbol_map.add_line(number, address);
+                address += instruction_size(mnemonic, &operands);
+                Statement::Instruction(mnemonic, operands)
+            }
+        };
+        lines.push(Line { number, statement });
+    }
+
+    // Second pass: every symbol is known now
+    let mut rom = Vec::new();
+    for line in lines {
+        match line.statement {
+            Statement::Instruction(mnemonic, operands) => {
+                let (instruction, long_address) =
+                    assembler.instruction(line.number, mnemonic, &operands)?;
+                rom.extend_from_slice(&instruction.encode().to_be_bytes());
+                if let Some(long_address) = long_address {
+                    rom.extend_from_slice(&long_address.to_be_bytes());
+                }
+            }
+            Statement::Bytes(values) => {
+                for value in values {
+                    match assembler.value(line.number, value)? {
+                        byte @ -128..=255 => rom.push(byte as u8),
+                        byte => {
+                            return Err(error(line.number, format!("byte {} out of range", byte)))
+                        }
+                    }
+                }
+            }
+            Statement::Words(values) => {
+                for value in values {
+                    match assembler.value(line.number, value)? {
+                        word @ -32768..=65535 => {
+                            rom.extend_from_slice(&(word as u16).to_be_bytes())
+                        }
+                        word => {
+                            return Err(error(line.number, format!("word {} out of range", word)))
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if PROGRAM_START + rom.len() > RAM {
+        return Err(error(
+            source.lines().count(),
+            "program does not fit in memory".to_string(),
+        ));
+    }
+    Ok((rom, symbol_map))
+}
+
+#[cfg(test)]
+#[path = "./assembler_test.rs"]
+mod test;
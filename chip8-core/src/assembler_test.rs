@@ -0,0 +1,71 @@
+use super::*;
+use crate::disassembler::Disassembly;
+
+#[test]
+fn assemble_test() {
+    let source = "
+        ; draw a sprite forever
+        X equ 10
+        main: LD V0, X
+            LD V1, X + 2
+            LD I, sprite
+            DRW V0, V1, 2
+            ADD V0, -1
+            SHR V2
+            LD I, LONG sprite
+            JP main
+        sprite:
+            db 0xF0, 0b10010000
+            dw 0x1234
+    ";
+    let rom = assemble(source).unwrap();
+    assert_eq!(
+        rom,
+        vec![
+            0x60, 0x0A, 0x61, 0x0C, 0xA2, 0x12, 0xD0, 0x12, 0x70, 0xFF, 0x82, 0x26, 0xF0, 0x00,
+            0x02, 0x12, 0x12, 0x00, 0xF0, 0x90, 0x12, 0x34,
+        ]
+    );
+}
+#[test]
+fn disassembly_round_trip_test() {
+    for rom in [
+        &include_bytes!("../../assets/pong.ch8")[..],
+        &include_bytes!("../../assets/octojam2title.ch8")[..],
+    ] {
+        let source = Disassembly::new(rom).source();
+        assert_eq!(assemble(&source).unwrap(), rom);
+    }
+}
+#[test]
+fn errors_test() {
+    let error = assemble("CLS\nLD V0, 256").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.to_string(), "line 2: byte 256 out of range");
+    let error = assemble("JP nowhere").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: unknown symbol `nowhere`");
+    let error = assemble("\n\nDRW V0, 5").unwrap_err();
+    assert_eq!(error.to_string(), "line 3: bad operands for DRW: `V0, 5`");
+    let error = assemble("MOV V0, V1").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: unknown mnemonic `MOV`");
+    let error = assemble("a:\na:").unwrap_err();
+    assert_eq!(error.to_string(), "line 2: `a` is defined twice");
+    let big = "BIG equ 0x7FFFFFFFFFFFFFFF\nLD V0, BIG + BIG";
+    let error = assemble(big).unwrap_err();
+    assert_eq!(error.to_string(), "line 2: `BIG + BIG` overflows");
+    let error = assemble("LD V0, -0x7FFFFFFFFFFFFFFF - 2").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 1: `-0x7FFFFFFFFFFFFFFF - 2` overflows"
+    );
+}
+
+#[test]
+fn symbols_test() {
+    let (_, symbols) =
+        assemble_with_symbols("main:\n  CLS\n  LD I, LONG data\ndata: db 1\n  JP main").unwrap();
+    assert_eq!(symbols.label_address("main"), Some(0x200));
+    assert_eq!(symbols.label_address("data"), Some(0x206));
+    assert_eq!(symbols.line_address(3), Some((3, 0x202)));
+    assert_eq!(symbols.line_address(4), Some((5, 0x207)));
+}
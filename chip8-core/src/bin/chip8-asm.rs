@@ -0,0 +1,64 @@
+use std::env;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+use std::process::exit;
+
+use chip8_core::assembler::assemble_with_symbols;
+
+const USAGE: &str = "usage: chip8-asm <source.asm> [-o <rom.ch8>] [--map <symbols.map>]";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut source_path = None;
+    let mut rom_path = None;
+    let mut map_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => rom_path = args.next(),
+            "--map" => map_path = args.next(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => source_path = Some(arg),
+        }
+    }
+    let source_path = match source_path {
+        Some(source_path) => source_path,
+        None => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    let rom_path = rom_path.unwrap_or_else(|| {
+        Path::new(&source_path)
+            .with_extension("ch8")
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    let source = match read_to_string(&source_path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("chip8-asm: {}: {}", source_path, error);
+            exit(1);
+        }
+    };
+    let (rom, symbols) = match assemble_with_symbols(&source) {
+        Ok(assembled) => assembled,
+        Err(error) => {
+            eprintln!("{}:{}", source_path, error);
+            exit(1);
+        }
+    };
+    if let Err(error) = write(&rom_path, rom) {
+        eprintln!("chip8-asm: {}: {}", rom_path, error);
+        exit(1);
+    }
+    if let Some(map_path) = map_path {
+        if let Err(error) = write(&map_path, symbols.to_text()) {
+            eprintln!("chip8-asm: {}: {}", map_path, error);
+            exit(1);
+        }
+    }
+}
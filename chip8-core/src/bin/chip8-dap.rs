@@ -0,0 +1,47 @@
+use std::env;
+use std::io::{stdin, stdout, BufReader};
+use std::net::TcpListener;
+use std::process::exit;
+
+use chip8_core::dap::serve;
+
+const USAGE: &str = "usage: chip8-dap [--port <port>]";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut port = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().and_then(|port| port.parse::<u16>().ok()) {
+                Some(value) => port = Some(value),
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                println!("  --port  serve one client on 127.0.0.1:<port> instead of stdio");
+                return;
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                exit(2);
+            }
+        }
+    }
+
+    let result = match port {
+        Some(port) => TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| listener.accept())
+            .and_then(|(stream, _)| {
+                let reader = BufReader::new(stream.try_clone()?);
+                serve(reader, stream)
+            }),
+        None => serve(BufReader::new(stdin()), stdout()),
+    };
+    if let Err(error) = result {
+        eprintln!("chip8-dap: {}", error);
+        exit(1);
+    }
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::fs::read;
+use std::process::exit;
+
+use chip8_core::disassembler::Disassembly;
+
+const USAGE: &str = "usage: chip8-disasm [--asm] <rom.ch8>";
+
+fn main() {
+    let mut source = false;
+    let mut rom_path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--asm" => source = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                println!("  --asm  print assembler source instead of a listing");
+                return;
+            }
+            _ => rom_path = Some(arg),
+        }
+    }
+    let rom_path = match rom_path {
+        Some(rom_path) => rom_path,
+        None => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    let rom = match read(&rom_path) {
+        Ok(rom) => rom,
+        Err(error) => {
+            eprintln!("chip8-disasm: {}: {}", rom_path, error);
+            exit(1);
+        }
+    };
+
+    let disassembly = Disassembly::new(&rom);
+    if source {
+        print!("{}", disassembly.source());
+    } else {
+        print!("{}", disassembly.listing());
+    }
+}
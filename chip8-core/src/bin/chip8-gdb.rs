@@ -0,0 +1,70 @@
+use std::env;
+use std::fs::read;
+use std::process::exit;
+
+use chip8_core::gdb::listen;
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::{Quirks, QUIRKS_PROFILES};
+
+const USAGE: &str = "usage: chip8-gdb <rom.ch8> [--port <port>] [--quirks <profile>]";
+const DEFAULT_PORT: u16 = 1234;
+const INSTRUCTIONS_PER_FRAME: usize = 9;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut rom_path = None;
+    let mut port = DEFAULT_PORT;
+    let mut quirks = Quirks::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().and_then(|port| port.parse().ok()) {
+                Some(value) => port = value,
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            "--quirks" => {
+                let name = args.next().unwrap_or_default();
+                match QUIRKS_PROFILES
+                    .iter()
+                    .find(|(profile, _)| profile.eq_ignore_ascii_case(&name))
+                {
+                    Some((_, profile)) => quirks = *profile,
+                    None => {
+                        eprintln!("chip8-gdb: unknown quirks profile `{}`", name);
+                        eprintln!("{}", USAGE);
+                        exit(2);
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => rom_path = Some(arg),
+        }
+    }
+    let rom_path = match rom_path {
+        Some(rom_path) => rom_path,
+        None => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    let rom = match read(&rom_path) {
+        Ok(rom) => rom,
+        Err(error) => {
+            eprintln!("chip8-gdb: {}: {}", rom_path, error);
+            exit(1);
+        }
+    };
+
+    let mut cpu = Cpu::new(quirks);
+    cpu.load_rom(&rom);
+    println!("chip8-gdb: waiting for a debugger on 127.0.0.1:{}", port);
+    if let Err(error) = listen(&mut cpu, port, INSTRUCTIONS_PER_FRAME) {
+        eprintln!("chip8-gdb: {}", error);
+        exit(1);
+    }
+}
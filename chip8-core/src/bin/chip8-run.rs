@@ -0,0 +1,152 @@
+use std::env;
+use std::fs::{read, read_to_string, write};
+use std::path::Path;
+use std::process::exit;
+
+use chip8_core::assembler::assemble;
+use chip8_core::headless::{HeadlessRun, KeyPress, RunEnd};
+use chip8_core::octo::compile;
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::{Quirks, QUIRKS_PROFILES};
+use chip8_core::random::Random;
+use chip8_core::screen_dump::{to_ascii, to_pbm, to_png};
+use chip8_core::trace::framebuffer_hash;
+
+const USAGE: &str = "usage: chip8-run --headless <rom> [options]
+  --frames <n>          frames to run, 60 per second, 600 by default
+  --until-pc <addr>     stop once the program counter reaches a hex address
+  --until-loop          stop at a jump to itself, how most test ROMs end
+  --key <frames>:<keys> hold hex keys during a frame or a range, e.g. 30-40:5A
+  --quirks <profile>    CHIP-8, COSMAC VIP, SUPER-CHIP or XO-CHIP
+  --seed <n>            seed for CXNN, 0 by default
+  --ipf <n>             instructions per frame, 9 by default
+  -o <file>             write the screen to a .txt, .pbm or .png file instead of printing it";
+const DEFAULT_FRAMES: usize = 600;
+const INSTRUCTIONS_PER_FRAME: usize = 9;
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("chip8-run: {}", message);
+    eprintln!("{}", USAGE);
+    exit(2);
+}
+
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> T {
+    match args.next().and_then(|value| value.parse().ok()) {
+        Some(value) => value,
+        None => usage_error(&format!("{} needs a value", name)),
+    }
+}
+
+// ROMs are loaded as they are, Octo and assembler sources are compiled first
+fn load_rom(path: &str) -> Result<Vec<u8>, String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    match extension {
+        Some("8o") | Some("asm") => {
+            let source = read_to_string(path).map_err(|error| error.to_string())?;
+            if extension == Some("8o") {
+                compile(&source).map_err(|error| error.to_string())
+            } else {
+                assemble(&source).map_err(|error| error.to_string())
+            }
+        }
+        _ => read(path).map_err(|error| error.to_string()),
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut rom_path = None;
+    let mut output = None;
+    let mut quirks = Quirks::default();
+    let mut seed = 0;
+    let mut run = HeadlessRun::new(DEFAULT_FRAMES, INSTRUCTIONS_PER_FRAME);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // there is no window to open in this tool, the flag keeps scripts explicit
+            "--headless" => {}
+            "--frames" => run.frames = value(&mut args, &arg),
+            "--ipf" => run.instructions_per_frame = value(&mut args, &arg),
+            "--seed" => seed = value(&mut args, &arg),
+            "--until-loop" => run.until_loop = true,
+            "--until-pc" => {
+                let address: String = value(&mut args, &arg);
+                match usize::from_str_radix(address.trim_start_matches("0x"), 16) {
+                    Ok(address) => run.until_program_counter = Some(address),
+                    Err(_) => usage_error("--until-pc needs a hex address"),
+                }
+            }
+            "--key" => {
+                let press: String = value(&mut args, &arg);
+                match KeyPress::parse(&press) {
+                    Some(press) => run.keys.push(press),
+                    None => usage_error(&format!("bad key press `{}`", press)),
+                }
+            }
+            "--quirks" => {
+                let name: String = value(&mut args, &arg);
+                match QUIRKS_PROFILES
+                    .iter()
+                    .find(|(profile, _)| profile.eq_ignore_ascii_case(&name))
+                {
+                    Some((_, profile)) => quirks = *profile,
+                    None => usage_error(&format!("unknown quirks profile `{}`", name)),
+                }
+            }
+            "-o" => output = Some(value::<String>(&mut args, &arg)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => rom_path = Some(arg),
+        }
+    }
+    let rom_path = rom_path.unwrap_or_else(|| usage_error("no rom given"));
+    let rom = load_rom(&rom_path).unwrap_or_else(|error| {
+        eprintln!("chip8-run: {}: {}", rom_path, error);
+        exit(1);
+    });
+
+    let mut cpu = Cpu::new(quirks);
+    cpu.set_random(Random::new(seed));
+    cpu.load_rom(&rom);
+    let (frames, end) = match run.run(&mut cpu) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("chip8-run: {}", error);
+            exit(1);
+        }
+    };
+
+    match &output {
+        Some(path) => {
+            let result = match Path::new(path)
+                .extension()
+                .and_then(|extension| extension.to_str())
+            {
+                Some("png") => write(path, to_png(&cpu)),
+                Some("pbm") => write(path, to_pbm(&cpu)),
+                _ => write(path, to_ascii(&cpu)),
+            };
+            if let Err(error) = result {
+                eprintln!("chip8-run: {}: {}", path, error);
+                exit(1);
+            }
+        }
+        None => print!("{}", to_ascii(&cpu)),
+    }
+    let end = match end {
+        RunEnd::Frames => "frames",
+        RunEnd::ProgramCounter => "pc",
+        RunEnd::Loop => "loop",
+        RunEnd::Halted => "halted",
+    };
+    println!(
+        "frames: {}  end: {}  pc: {:04X}  hash: {:08X}",
+        frames,
+        end,
+        cpu.program_counter(),
+        framebuffer_hash(&cpu)
+    );
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::fs::read;
+use std::process::exit;
+
+use chip8_core::trace::{read_trace, TraceRecord};
+use chip8_core::trace_diff::{first_divergence, report};
+
+const USAGE: &str = "usage: chip8-tracediff [-C <lines>] <left.trace> <right.trace>";
+const DEFAULT_CONTEXT: usize = 5;
+
+fn load(path: &str) -> Vec<TraceRecord> {
+    let data = match read(path) {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("chip8-tracediff: {}: {}", path, error);
+            exit(2);
+        }
+    };
+    match read_trace(&data) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("chip8-tracediff: {}: {}", path, error);
+            exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut context = DEFAULT_CONTEXT;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-C" | "--context" => match args.next().and_then(|lines| lines.parse().ok()) {
+                Some(lines) => context = lines,
+                None => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                println!("  -C  records of context before the divergence, 5 by default");
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+
+    let left = load(&paths[0]);
+    let right = load(&paths[1]);
+    match first_divergence(&left, &right) {
+        Some(divergence) => {
+            print!(
+                "{}",
+                report((&paths[0], &paths[1]), &left, &right, &divergence, context)
+            );
+            exit(1);
+        }
+        None => println!("traces match, {} records", left.len()),
+    }
+}
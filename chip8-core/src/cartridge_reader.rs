@@ -0,0 +1,154 @@
+use std::fmt;
+use std::fs::{metadata, read, read_dir, read_to_string, ReadDir};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::octo::{compile, OctoError};
+use crate::save_state::rom_hash;
+
+// Why the picked game could not be turned into a ROM
+#[derive(Debug)]
+pub enum CartridgeError {
+    Io(io::Error),
+    Octo(OctoError),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::Io(error) => write!(f, "{}", error),
+            CartridgeError::Octo(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+impl From<io::Error> for CartridgeError {
+    fn from(error: io::Error) -> CartridgeError {
+        CartridgeError::Io(error)
+    }
+}
+
+impl From<OctoError> for CartridgeError {
+    fn from(error: OctoError) -> CartridgeError {
+        CartridgeError::Octo(error)
+    }
+}
+
+pub struct Cartridge {
+    pub game_paths: Vec<PathBuf>,
+    pub rom: Vec<u8>,
+    choosen_game: PathBuf,
+    game_index: usize,
+}
+
+impl Default for Cartridge {
+    fn default() -> Cartridge {
+        Cartridge::new()
+    }
+}
+
+impl Cartridge {
+    // Without any games, see from_paths
+    pub fn new() -> Cartridge {
+        Cartridge {
+            game_paths: Vec::<PathBuf>::new(),
+            rom: Vec::<u8>::new(),
+            choosen_game: PathBuf::new(),
+            game_index: 0,
+        }
+    }
+    // Every .ch8 and .8o file in the directories, and the files given directly
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<Cartridge> {
+        let mut cartridge = Cartridge::new();
+        for path in paths {
+            let path = path.as_ref();
+            if path.is_dir() {
+                cartridge.load_game_paths(read_dir(path)?)?;
+            } else {
+                metadata(path)?;
+                cartridge.game_paths.push(path.to_path_buf());
+            }
+        }
+        if cartridge.game_paths.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no .ch8 or .8o files found",
+            ));
+        }
+        cartridge.choosen_game = cartridge.game_paths[cartridge.game_index].clone();
+        // a broken .8o source only reports its error once it is picked in the loader
+        cartridge.game_to_rom().ok();
+        Ok(cartridge)
+    }
+    fn load_game_paths(&mut self, dir: ReadDir) -> io::Result<()> {
+        for path in dir {
+            let file_path = path?.path();
+            let file_extension = file_path.extension().unwrap_or_default();
+            if file_extension == "ch8" || file_extension == "8o" {
+                self.game_paths.push(file_path);
+            }
+        }
+        Ok(())
+    }
+    // .8o files are Octo sources and get compiled on load.
+    // The files are only listed up front, they can be gone or unreadable by the time they are picked
+    pub fn game_to_rom(&mut self) -> Result<(), CartridgeError> {
+        if self.choosen_game.extension().unwrap_or_default() == "8o" {
+            let source = read_to_string(&self.choosen_game)?;
+            self.rom = compile(&source)?;
+        } else {
+            self.rom = read(&self.choosen_game)?;
+        }
+        Ok(())
+    }
+    // The file name without directory and extension, also names save states and movies.
+    // Characters that are not valid UTF-8 are replaced
+    pub fn get_game_name(&self) -> String {
+        self.choosen_game
+            .file_stem()
+            .unwrap_or(self.choosen_game.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    }
+    // Identifies the loaded ROM no matter what its file is called, as 40 hex digits
+    pub fn rom_sha1(&self) -> String {
+        rom_hash(&self.rom)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+    pub fn next_game(&mut self) {
+        if self.game_paths.is_empty() {
+            return;
+        }
+        let game_count = self.game_paths.len();
+        let game_array_len = game_count - 1;
+        self.game_index = if self.game_index == game_array_len {
+            0
+        } else {
+            self.game_index + 1
+        };
+        let index = self.game_index;
+        self.choosen_game = self.game_paths[index].clone();
+    }
+    pub fn previous_game(&mut self) {
+        if self.game_paths.is_empty() {
+            return;
+        }
+        let game_count = self.game_paths.len();
+        let game_array_len = game_count - 1;
+        self.game_index = if self.game_index == 0 {
+            game_array_len
+        } else {
+            self.game_index - 1
+        };
+        let index = self.game_index;
+        self.choosen_game = self.game_paths[index].clone();
+    }
+}
+
+#[cfg(test)]
+#[path = "./cartridge_reader_test.rs"]
+mod test;
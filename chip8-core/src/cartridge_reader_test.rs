@@ -0,0 +1,26 @@
+use super::*;
+use std::fs::{remove_file, write};
+
+#[test]
+fn missing_game_test() {
+    let path = std::env::temp_dir().join(format!("chip8-cartridge-{}.ch8", std::process::id()));
+    write(&path, [0x00, 0xE0]).unwrap();
+    let mut cartridge = Cartridge::from_paths(&[&path]).unwrap();
+    assert_eq!(cartridge.rom, [0x00, 0xE0]);
+
+    // listed, then deleted before it is picked
+    remove_file(&path).unwrap();
+    assert!(matches!(
+        cartridge.game_to_rom(),
+        Err(CartridgeError::Io(_))
+    ));
+    assert!(Cartridge::from_paths(&[&path]).is_err());
+}
+
+#[test]
+fn empty_cartridge_test() {
+    let mut cartridge = Cartridge::new();
+    cartridge.next_game();
+    cartridge.previous_game();
+    assert!(cartridge.game_paths.is_empty());
+}
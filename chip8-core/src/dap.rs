@@ -0,0 +1,453 @@
+use std::fs::{canonicalize, read, read_to_string};
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::assembler::assemble_with_symbols;
+use crate::debugger::Debugger;
+use crate::octo::compile_with_symbols;
+use crate::processor::{Cpu, CpuError, StepOutcome};
+use crate::quirks::Quirks;
+use crate::symbols::SymbolMap;
+use crate::TIMER_RATE;
+
+// Debug Adapter Protocol server, the Cpu is a single thread.
+// launch takes the program to run: a ROM, an Octo (.8o) or assembler (.asm) source.
+// Sources are compiled with a symbol map so breakpoints can be set by line, a ROM can
+// bring its map along with `symbols` and the source file it was built from with `source`
+const THREAD_ID: u64 = 1;
+const REGISTERS_REFERENCE: u64 = 1;
+const TIMERS_REFERENCE: u64 = 2;
+const INSTRUCTIONS_PER_FRAME: usize = 9;
+
+// Serves one client until it disconnects. Works on stdio as well as on a socket
+pub fn serve<R, W>(reader: R, writer: W) -> io::Result<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+{
+    let (sender, messages) = channel();
+    // requests are read on their own thread so pause can arrive while the Cpu runs
+    thread::spawn(move || {
+        let mut reader = reader;
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    let mut session = Session {
+        writer,
+        seq: 0,
+        cpu: Cpu::new(Quirks::default()),
+        debugger: Debugger::new(),
+        symbols: SymbolMap::new(),
+        source: None,
+        line_breakpoints: Vec::new(),
+        instruction_breakpoints: Vec::new(),
+        stop_on_entry: false,
+        events: Vec::new(),
+    };
+    session.run(messages)
+}
+
+// Content-Length framed JSON, None once the client closed the stream
+fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "no length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+}
+
+fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+struct Session<W: Write> {
+    writer: W,
+    seq: u64,
+    cpu: Cpu,
+    debugger: Debugger,
+    symbols: SymbolMap,
+    // the source file the symbol map belongs to
+    source: Option<String>,
+    line_breakpoints: Vec<usize>,
+    instruction_breakpoints: Vec<usize>,
+    stop_on_entry: bool,
+    // sent after the response to the request being handled
+    events: Vec<(&'static str, Value)>,
+}
+
+impl<W: Write> Session<W> {
+    fn run(&mut self, messages: Receiver<Value>) -> io::Result<()> {
+        loop {
+            // while running one frame is executed every time no request arrives in time
+            let message = if self.debugger.paused {
+                match messages.recv() {
+                    Ok(message) => message,
+                    Err(_) => return Ok(()),
+                }
+            } else {
+                match messages.recv_timeout(Duration::from_micros(TIMER_RATE)) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => {
+                        let result = self
+                            .debugger
+                            .run_frame(&mut self.cpu, INSTRUCTIONS_PER_FRAME);
+                        self.check_stop(result);
+                        self.send_events()?;
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            };
+            if message["type"] == "request" && !self.request(&message)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn send_events(&mut self) -> io::Result<()> {
+        for (event, body) in std::mem::take(&mut self.events) {
+            self.send(json!({ "type": "event", "event": event, "body": body }))?;
+        }
+        Ok(())
+    }
+
+    // Handles one request, false once the client asked to disconnect
+    fn request(&mut self, request: &Value) -> io::Result<bool> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let result = match command {
+            "initialize" => {
+                self.events.push(("initialized", json!({})));
+                Ok(json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsInstructionBreakpoints": true,
+                    "supportsSteppingGranularity": false,
+                }))
+            }
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => Ok(self.set_breakpoints(arguments)),
+            "setInstructionBreakpoints" => Ok(self.set_instruction_breakpoints(arguments)),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => {
+                if self.stop_on_entry {
+                    self.stopped("entry", None);
+                } else {
+                    self.debugger.resume();
+                }
+                Ok(Value::Null)
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+            "stackTrace" => Ok(self.stack_trace()),
+            "scopes" => Ok(json!({
+                "scopes": [
+                    { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+                    { "name": "Timers", "variablesReference": TIMERS_REFERENCE, "expensive": false },
+                ]
+            })),
+            "variables" => Ok(self.variables(arguments["variablesReference"].as_u64())),
+            "continue" => {
+                self.debugger.watch_hit = None;
+                self.debugger.resume();
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" | "stepIn" | "stepOut" => {
+                self.debugger.watch_hit = None;
+                let result = match command {
+                    "next" => self.debugger.step_over(&mut self.cpu),
+                    "stepIn" => self.debugger.step(&mut self.cpu),
+                    _ => self.debugger.step_out(&mut self.cpu),
+                };
+                self.check_stop(result);
+                Ok(Value::Null)
+            }
+            "pause" => {
+                self.debugger.pause();
+                self.stopped("pause", None);
+                Ok(Value::Null)
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(Value::Null))?;
+                return Ok(false);
+            }
+            _ => Err(format!("unsupported request {}", command)),
+        };
+        self.respond(request, result)?;
+        self.send_events()?;
+        Ok(true)
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                response["success"] = json!(true);
+                if !body.is_null() {
+                    response["body"] = body;
+                }
+            }
+            Err(message) => {
+                response["success"] = json!(false);
+                response["message"] = json!(message);
+            }
+        }
+        self.send(response)
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.events.push(("stopped", body));
+    }
+
+    // Reports why the Cpu stopped after a step or a frame, if it did
+    fn check_stop(&mut self, result: Result<StepOutcome, CpuError>) {
+        match result {
+            Ok(StepOutcome::Halted) => {
+                self.debugger.pause();
+                self.events.push(("exited", json!({ "exitCode": 0 })));
+                self.events.push(("terminated", json!({})));
+            }
+            Err(error) => {
+                self.debugger.pause();
+                self.stopped("exception", Some(error.to_string()));
+            }
+            Ok(_) if self.debugger.paused => {
+                let reason = if self.debugger.watch_hit.is_some() {
+                    "data breakpoint"
+                } else if self.debugger.is_breakpoint(self.cpu.program_counter()) {
+                    "breakpoint"
+                } else {
+                    "step"
+                };
+                self.stopped(reason, None);
+            }
+            Ok(_) => {}
+        }
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = arguments["program"]
+            .as_str()
+            .ok_or_else(|| "launch needs a program".to_string())?;
+        let extension = Path::new(program)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        let (rom, symbols, source) = match extension {
+            "8o" | "asm" => {
+                let text = read_to_string(program).map_err(|error| error.to_string())?;
+                let (rom, symbols) = if extension == "8o" {
+                    compile_with_symbols(&text).map_err(|error| error.to_string())?
+                } else {
+                    assemble_with_symbols(&text).map_err(|error| error.to_string())?
+                };
+                (rom, symbols, Some(program.to_string()))
+            }
+            _ => {
+                let rom = read(program).map_err(|error| error.to_string())?;
+                let symbols = match arguments["symbols"].as_str() {
+                    Some(path) => {
+                        let text = read_to_string(path).map_err(|error| error.to_string())?;
+                        SymbolMap::from_text(&text).map_err(|error| error.to_string())?
+                    }
+                    None => SymbolMap::new(),
+                };
+                (
+                    rom,
+                    symbols,
+                    arguments["source"].as_str().map(str::to_string),
+                )
+            }
+        };
+        self.cpu = Cpu::new(Quirks::default());
+        self.cpu.load_rom(&rom);
+        self.symbols = symbols;
+        self.source = source;
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        Ok(Value::Null)
+    }
+
+    fn is_source(&self, path: &str) -> bool {
+        match &self.source {
+            Some(source) => {
+                source == path
+                    || matches!((canonicalize(source), canonicalize(path)), (Ok(a), Ok(b)) if a == b)
+            }
+            None => false,
+        }
+    }
+
+    fn update_breakpoints(&mut self, old: Vec<usize>) {
+        for address in old {
+            self.debugger.remove_breakpoint(address);
+        }
+        for address in self
+            .line_breakpoints
+            .iter()
+            .chain(&self.instruction_breakpoints)
+        {
+            self.debugger.add_breakpoint(*address);
+        }
+    }
+
+    // Lines without code move to the next line that has some, like in other debuggers
+    fn set_breakpoints(&mut self, arguments: &Value) -> Value {
+        let path = arguments["source"]["path"].as_str().unwrap_or_default();
+        let is_source = self.is_source(path);
+        let mut breakpoints = Vec::new();
+        let mut addresses = Vec::new();
+        for breakpoint in arguments["breakpoints"].as_array().into_iter().flatten() {
+            let line = breakpoint["line"].as_u64().unwrap_or_default() as usize;
+            match self.symbols.line_address(line).filter(|_| is_source) {
+                Some((line, address)) => {
+                    addresses.push(address);
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "line": line,
+                        "instructionReference": format!("0x{:04X}", address),
+                    }));
+                }
+                None => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "no code at this line",
+                })),
+            }
+        }
+        let old = std::mem::replace(&mut self.line_breakpoints, addresses);
+        self.update_breakpoints(old);
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn set_instruction_breakpoints(&mut self, arguments: &Value) -> Value {
+        let mut breakpoints = Vec::new();
+        let mut addresses = Vec::new();
+        for breakpoint in arguments["breakpoints"].as_array().into_iter().flatten() {
+            let reference = breakpoint["instructionReference"]
+                .as_str()
+                .unwrap_or_default();
+            let offset = breakpoint["offset"].as_i64().unwrap_or_default();
+            let address = usize::from_str_radix(reference.trim_start_matches("0x"), 16)
+                .ok()
+                .and_then(|address| address.checked_add_signed(offset as isize));
+            match address {
+                Some(address) => {
+                    addresses.push(address);
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "instructionReference": format!("0x{:04X}", address),
+                    }));
+                }
+                None => breakpoints.push(json!({ "verified": false })),
+            }
+        }
+        let old = std::mem::replace(&mut self.instruction_breakpoints, addresses);
+        self.update_breakpoints(old);
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn frame(&self, id: usize, address: usize) -> Value {
+        let (mnemonic, _) = Debugger::disassemble(&self.cpu, address);
+        let name = match self.symbols.label_at(address) {
+            Some(label) => format!("{}: {}", label, mnemonic),
+            None => format!("{:04X} {}", address, mnemonic),
+        };
+        let mut frame = json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": format!("0x{:04X}", address),
+        });
+        if let (Some(source), Some(line)) = (&self.source, self.symbols.line_at(address)) {
+            frame["source"] = json!({ "path": source });
+            frame["line"] = json!(line);
+            frame["column"] = json!(1);
+        }
+        frame
+    }
+
+    // The current instruction first, then every call on the stack from the innermost
+    fn stack_trace(&self) -> Value {
+        let stack_pointer = self.cpu.stack_pointer() as usize;
+        let mut frames = vec![self.frame(0, self.cpu.program_counter())];
+        for depth in (0..stack_pointer).rev() {
+            let call = self.cpu.stack()[depth] as usize;
+            frames.push(self.frame(stack_pointer - depth, call));
+        }
+        json!({ "stackFrames": frames, "totalFrames": stack_pointer + 1 })
+    }
+
+    fn variables(&self, reference: Option<u64>) -> Value {
+        let cpu = &self.cpu;
+        let variables: Vec<(String, String)> = match reference {
+            Some(REGISTERS_REFERENCE) => {
+                let mut registers: Vec<(String, String)> = cpu
+                    .registers()
+                    .iter()
+                    .enumerate()
+                    .map(|(x, value)| (format!("V{:X}", x), format!("0x{:02X}", value)))
+                    .collect();
+                registers.push(("I".to_string(), format!("0x{:04X}", cpu.i())));
+                registers.push(("PC".to_string(), format!("0x{:04X}", cpu.program_counter())));
+                registers.push(("SP".to_string(), cpu.stack_pointer().to_string()));
+                registers
+            }
+            Some(TIMERS_REFERENCE) => vec![
+                ("DT".to_string(), cpu.delay_timer().to_string()),
+                ("ST".to_string(), cpu.check_sound_timer().to_string()),
+            ],
+            _ => Vec::new(),
+        };
+        let variables: Vec<Value> = variables
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "variablesReference": 0 }))
+            .collect();
+        json!({ "variables": variables })
+    }
+}
+
+#[cfg(test)]
+#[path = "./dap_test.rs"]
+mod test;
@@ -0,0 +1,135 @@
+use super::*;
+use std::collections::VecDeque;
+use std::io::{pipe, BufReader, PipeReader, PipeWriter};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const SOURCE: &str = "main:
+    LD V0, 1
+loop:
+    ADD V0, 1
+    CALL sub
+    JP loop
+sub:
+    RET
+";
+
+// A file of its own for every test, parallel runs of the suite included
+fn temp_source() -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!(
+        "chip8-dap-test-{}-{}.asm",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, SOURCE).unwrap();
+    path
+}
+
+struct Client {
+    writer: PipeWriter,
+    reader: BufReader<PipeReader>,
+    seq: u64,
+    events: VecDeque<Value>,
+}
+
+impl Client {
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let request = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        write_message(&mut self.writer, &request).unwrap();
+        loop {
+            let message = read_message(&mut self.reader).unwrap().unwrap();
+            if message["type"] == "response" {
+                assert_eq!(message["request_seq"], self.seq);
+                assert_eq!(message["success"], true, "{}", message);
+                return message["body"].clone();
+            }
+            self.events.push_back(message);
+        }
+    }
+
+    fn event(&mut self, event: &str) -> Value {
+        loop {
+            let message = match self.events.pop_front() {
+                Some(message) => message,
+                None => read_message(&mut self.reader).unwrap().unwrap(),
+            };
+            if message["event"] == event {
+                return message["body"].clone();
+            }
+        }
+    }
+
+    fn top_frame(&mut self) -> Value {
+        self.request("stackTrace", json!({ "threadId": THREAD_ID }))["stackFrames"][0].clone()
+    }
+}
+
+#[test]
+fn debug_session_test() {
+    let source = temp_source();
+    let path = source.to_string_lossy().into_owned();
+
+    let (request_reader, request_writer) = pipe().unwrap();
+    let (response_reader, response_writer) = pipe().unwrap();
+    let server = thread::spawn(move || serve(BufReader::new(request_reader), response_writer));
+    let mut client = Client {
+        writer: request_writer,
+        reader: BufReader::new(response_reader),
+        seq: 0,
+        events: VecDeque::new(),
+    };
+
+    client.request("initialize", json!({ "adapterID": "chip8" }));
+    client.event("initialized");
+    client.request("launch", json!({ "program": path }));
+    let breakpoints = client.request(
+        "setBreakpoints",
+        json!({ "source": { "path": path }, "breakpoints": [{ "line": 5 }, { "line": 7 }] }),
+    );
+    assert_eq!(
+        breakpoints["breakpoints"][0]["instructionReference"],
+        "0x0204"
+    );
+    // line 7 only holds a label, the breakpoint moves to the RET below it
+    assert_eq!(breakpoints["breakpoints"][1]["line"], 8);
+    client.request("configurationDone", json!({}));
+
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    let frame = client.top_frame();
+    assert_eq!(frame["line"], 5);
+    assert_eq!(frame["instructionPointerReference"], "0x0204");
+    let registers = client.request("variables", json!({ "variablesReference": 1 }));
+    assert_eq!(registers["variables"][0]["name"], "V0");
+    assert_eq!(registers["variables"][0]["value"], "0x02");
+
+    client.request("stepIn", json!({ "threadId": THREAD_ID }));
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    let trace = client.request("stackTrace", json!({ "threadId": THREAD_ID }));
+    assert_eq!(trace["totalFrames"], 2);
+    assert_eq!(trace["stackFrames"][0]["name"], "sub: RET");
+    assert_eq!(trace["stackFrames"][1]["line"], 5);
+
+    client.request("stepOut", json!({ "threadId": THREAD_ID }));
+    assert_eq!(client.event("stopped")["reason"], "step");
+    assert_eq!(client.top_frame()["line"], 6);
+
+    client.request(
+        "setBreakpoints",
+        json!({ "source": { "path": path }, "breakpoints": [] }),
+    );
+    client.request("continue", json!({ "threadId": THREAD_ID }));
+    client.request("pause", json!({ "threadId": THREAD_ID }));
+    assert_eq!(client.event("stopped")["reason"], "pause");
+
+    client.request("disconnect", json!({}));
+    server.join().unwrap().unwrap();
+    std::fs::remove_file(source).unwrap();
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeSet;
+
+use crate::instruction::Instruction;
+use crate::processor::{Cpu, CpuError, StepOutcome};
+use crate::watchpoint::WatchHit;
+use crate::RAM;
+
+// Execution control on top of a Cpu: pausing, stepping, PC breakpoints and run-to targets.
+// While running, breakpoints are checked before every instruction, so the Cpu stops with the
+// program counter on the breakpoint and the instruction there not executed yet
+pub struct Debugger {
+    pub paused: bool,
+    // the last watchpoint that paused execution
+    pub watch_hit: Option<WatchHit>,
+    breakpoints: BTreeSet<usize>,
+    // address to stop at, and the stack depth it has to be reached with for step over
+    target: Option<(usize, Option<u8>)>,
+    // lets resume() leave a breakpoint the Cpu is currently stopped on
+    skip_breakpoint: bool,
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            paused: true,
+            watch_hit: None,
+            breakpoints: BTreeSet::new(),
+            target: None,
+            skip_breakpoint: false,
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, address: usize) {
+        if !self.breakpoints.remove(&address) {
+            self.breakpoints.insert(address);
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) {
+        self.breakpoints.remove(&address);
+    }
+
+    pub fn is_breakpoint(&self, address: usize) -> bool {
+        self.breakpoints.contains(&address)
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.target = None;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.skip_breakpoint = true;
+    }
+
+    pub fn step(&mut self, cpu: &mut Cpu) -> Result<StepOutcome, CpuError> {
+        self.pause();
+        let outcome = cpu.run_next_instruction()?;
+        self.check_watch_hit(outcome);
+        Ok(outcome)
+    }
+
+    fn check_watch_hit(&mut self, outcome: StepOutcome) {
+        if let StepOutcome::Watchpoint(hit) = outcome {
+            self.watch_hit = Some(hit);
+            self.pause();
+        }
+    }
+
+    // Runs a whole subroutine when the next instruction is a call, otherwise the same as step
+    pub fn step_over(&mut self, cpu: &mut Cpu) -> Result<StepOutcome, CpuError> {
+        match Debugger::instruction_at(cpu, cpu.program_counter()) {
+            Some((Instruction::Call(_), _)) => {
+                self.target = Some((cpu.program_counter() + 2, Some(cpu.stack_pointer())));
+                self.resume();
+                Ok(StepOutcome::Executed)
+            }
+            _ => self.step(cpu),
+        }
+    }
+
+    // Runs until the current subroutine returns, the same as step when not in one
+    pub fn step_out(&mut self, cpu: &mut Cpu) -> Result<StepOutcome, CpuError> {
+        match cpu.stack_pointer() {
+            0 => self.step(cpu),
+            stack_pointer => {
+                let call = cpu.stack()[stack_pointer as usize - 1] as usize;
+                self.target = Some((call + 2, Some(stack_pointer - 1)));
+                self.resume();
+                Ok(StepOutcome::Executed)
+            }
+        }
+    }
+
+    pub fn run_to(&mut self, address: usize) {
+        self.target = Some((address, None));
+        self.resume();
+    }
+
+    fn should_stop(&self, cpu: &Cpu) -> bool {
+        let program_counter = cpu.program_counter();
+        if let Some((address, stack_pointer)) = self.target {
+            if program_counter == address
+                && stack_pointer.is_none_or(|stack_pointer| stack_pointer == cpu.stack_pointer())
+            {
+                return true;
+            }
+        }
+        !self.skip_breakpoint && self.breakpoints.contains(&program_counter)
+    }
+
+    // Cpu::run_frame that pauses on breakpoints, targets and watchpoints, does nothing while paused
+    pub fn run_frame(
+        &mut self,
+        cpu: &mut Cpu,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, CpuError> {
+        if self.paused {
+            return Ok(StepOutcome::Executed);
+        }
+        let mut outcome = StepOutcome::Executed;
+        for _ in 0..instructions_per_frame {
+            if self.should_stop(cpu) {
+                self.pause();
+                break;
+            }
+            self.skip_breakpoint = false;
+            outcome = cpu.run_next_instruction()?;
+            if outcome != StepOutcome::Executed {
+                break;
+            }
+        }
+        self.check_watch_hit(outcome);
+        cpu.tick_timers();
+        Ok(outcome)
+    }
+
+    // The instruction stored at an address and its size in bytes
+    pub fn instruction_at(cpu: &Cpu, address: usize) -> Option<(Instruction, usize)> {
+        let memory = cpu.memory();
+        if address + 1 >= RAM {
+            return None;
+        }
+        let opcode = (memory[address] as u16) << 8 | memory[address + 1] as u16;
+        let instruction = Instruction::decode(opcode)?;
+        Some((instruction, instruction.size()))
+    }
+
+    // Mnemonic for the instruction at an address, with the address of F000 NNNN filled in
+    pub fn disassemble(cpu: &Cpu, address: usize) -> (String, usize) {
+        let memory = cpu.memory();
+        match Debugger::instruction_at(cpu, address) {
+            Some((Instruction::LoadILong, size)) if address + 3 < RAM => {
+                let long = (memory[address + 2] as u16) << 8 | memory[address + 3] as u16;
+                (format!("LD I, LONG 0x{:04X}", long), size)
+            }
+            Some((instruction, size)) => (instruction.to_string(), size),
+            None if address + 1 < RAM => (
+                format!(
+                    "db 0x{:02X}, 0x{:02X}",
+                    memory[address],
+                    memory[address + 1]
+                ),
+                2,
+            ),
+            None => (String::new(), 2),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./debugger_test.rs"]
+mod test;
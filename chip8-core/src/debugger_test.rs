@@ -0,0 +1,73 @@
+use super::*;
+use crate::quirks::Quirks;
+
+// 0x200: call 0x208, V0 += 1, jump 0x202, nop padding, 0x208: V1 += 1, return
+const ROM: [u8; 12] = [
+    0x22, 0x08, 0x70, 0x01, 0x12, 0x02, 0x00, 0x00, 0x71, 0x01, 0x00, 0xEE,
+];
+
+fn cpu() -> Cpu {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    cpu
+}
+
+#[test]
+fn paused_does_nothing_test() {
+    let mut cpu = cpu();
+    let mut debugger = Debugger::new();
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert_eq!(cpu.program_counter(), 0x200);
+}
+
+#[test]
+fn breakpoint_test() {
+    let mut cpu = cpu();
+    let mut debugger = Debugger::new();
+    debugger.toggle_breakpoint(0x204);
+    debugger.resume();
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert!(debugger.paused);
+    assert_eq!(cpu.program_counter(), 0x204);
+
+    // resuming leaves the breakpoint and stops on it again one loop later
+    debugger.resume();
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert_eq!(cpu.program_counter(), 0x204);
+    assert_eq!(cpu.registers()[0], 2);
+
+    debugger.toggle_breakpoint(0x204);
+    assert!(!debugger.is_breakpoint(0x204));
+}
+
+#[test]
+fn step_and_step_over_test() {
+    let mut cpu = cpu();
+    let mut debugger = Debugger::new();
+    debugger.step(&mut cpu).unwrap();
+    assert_eq!(cpu.program_counter(), 0x208);
+    assert_eq!(cpu.stack_pointer(), 1);
+    debugger.step_out(&mut cpu).unwrap();
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert!(debugger.paused);
+    assert_eq!(cpu.program_counter(), 0x202);
+    assert_eq!(cpu.stack_pointer(), 0);
+
+    let mut cpu = self::cpu();
+    debugger.step_over(&mut cpu).unwrap();
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert!(debugger.paused);
+    assert_eq!(cpu.program_counter(), 0x202);
+    assert_eq!(cpu.registers()[1], 1);
+}
+
+#[test]
+fn run_to_test() {
+    let mut cpu = cpu();
+    let mut debugger = Debugger::new();
+    debugger.run_to(0x20A);
+    debugger.run_frame(&mut cpu, 10).unwrap();
+    assert!(debugger.paused);
+    assert_eq!(cpu.program_counter(), 0x20A);
+    assert_eq!(Debugger::disassemble(&cpu, 0x20A), ("RET".to_string(), 2));
+}
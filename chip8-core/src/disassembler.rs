@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::instruction::Instruction;
+use crate::PROGRAM_START;
+
+const DATA_ROW: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    // long_address is the second word of F000 NNNN
+    Code {
+        address: usize,
+        instruction: Instruction,
+        long_address: Option<u16>,
+    },
+    Data {
+        address: usize,
+        bytes: Vec<u8>,
+    },
+}
+
+impl Item {
+    pub fn address(&self) -> usize {
+        match self {
+            Item::Code { address, .. } => *address,
+            Item::Data { address, .. } => *address,
+        }
+    }
+}
+
+// A ROM split into instructions and data.
+// Only bytes reachable from PROGRAM_START by following jumps, calls and skips count as code,
+// everything else (sprites, tables, unreachable leftovers) is shown as data
+pub struct Disassembly {
+    pub items: Vec<Item>,
+    pub labels: BTreeMap<usize, String>,
+}
+
+impl Disassembly {
+    pub fn new(rom: &[u8]) -> Disassembly {
+        let end = PROGRAM_START + rom.len();
+        let read_word = |address: usize| -> Option<u16> {
+            if address >= PROGRAM_START && address + 1 < end {
+                let offset = address - PROGRAM_START;
+                Some((rom[offset] as u16) << 8 | rom[offset + 1] as u16)
+            } else {
+                None
+            }
+        };
+
+        let mut code = BTreeMap::new();
+        let mut is_code = vec![false; rom.len()];
+        let mut calls = BTreeSet::new();
+        let mut jumps = BTreeSet::new();
+        let mut data = BTreeSet::new();
+        let mut pending = vec![PROGRAM_START];
+
+        while let Some(address) = pending.pop() {
+            let instruction = match read_word(address).and_then(Instruction::decode) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            let long_address = match instruction {
+                Instruction::LoadILong => match read_word(address + 2) {
+                    Some(long_address) => Some(long_address),
+                    None => continue,
+                },
+                _ => None,
+            };
+            let size = instruction.size();
+            let offset = address - PROGRAM_START;
+            if is_code[offset..offset + size].iter().any(|byte| *byte) {
+                continue;
+            }
+            is_code[offset..offset + size].fill(true);
+            code.insert(address, (instruction, long_address));
+
+            let next = address + size;
+            match instruction {
+                Instruction::Jump(nnn) => {
+                    jumps.insert(nnn as usize);
+                    pending.push(nnn as usize);
+                }
+                Instruction::Call(nnn) => {
+                    calls.insert(nnn as usize);
+                    pending.push(nnn as usize);
+                    pending.push(next);
+                }
+                Instruction::Return | Instruction::Exit | Instruction::JumpWithOffset(_) => {}
+                Instruction::SkipIfEqual(..)
+                | Instruction::SkipIfNotEqual(..)
+                | Instruction::SkipIfRegistersEqual(..)
+                | Instruction::SkipIfRegistersNotEqual(..)
+                | Instruction::SkipIfPressed(_)
+                | Instruction::SkipIfNotPressed(_) => {
+                    let skipped = match read_word(next) {
+                        Some(0xF000) => 4,
+                        _ => 2,
+                    };
+                    pending.push(next + skipped);
+                    pending.push(next);
+                }
+                Instruction::LoadI(nnn) => {
+                    data.insert(nnn as usize);
+                    pending.push(next);
+                }
+                _ => {
+                    if let Some(long_address) = long_address {
+                        data.insert(long_address as usize);
+                    }
+                    pending.push(next);
+                }
+            }
+        }
+
+        let mut labels = BTreeMap::new();
+        for address in data {
+            labels.insert(address, format!("data_{:03X}", address));
+        }
+        for address in jumps {
+            labels.insert(address, format!("label_{:03X}", address));
+        }
+        for address in calls {
+            labels.insert(address, format!("sub_{:03X}", address));
+        }
+
+        let mut items = Vec::new();
+        let mut address = PROGRAM_START;
+        while address < end {
+            if let Some((instruction, long_address)) = code.get(&address) {
+                items.push(Item::Code {
+                    address,
+                    instruction: *instruction,
+                    long_address: *long_address,
+                });
+                address += instruction.size();
+                continue;
+            }
+            let mut bytes = Vec::new();
+            while address < end && !is_code[address - PROGRAM_START] && bytes.len() < DATA_ROW {
+                if !bytes.is_empty() && labels.contains_key(&address) {
+                    break;
+                }
+                bytes.push(rom[address - PROGRAM_START]);
+                address += 1;
+            }
+            items.push(Item::Data {
+                address: address - bytes.len(),
+                bytes,
+            });
+        }
+
+        // labels pointing into the middle of an item or outside the ROM can't be printed
+        let starts: BTreeSet<usize> = items.iter().map(Item::address).collect();
+        labels.retain(|address, _| starts.contains(address));
+
+        Disassembly { items, labels }
+    }
+
+    fn address_operand(&self, address: usize) -> String {
+        match self.labels.get(&address) {
+            Some(label) => label.clone(),
+            None => format!("0x{:03X}", address),
+        }
+    }
+
+    // Instruction text with known addresses replaced by labels
+    pub fn mnemonic(&self, item: &Item) -> String {
+        match item {
+            Item::Code {
+                instruction,
+                long_address,
+                ..
+            } => match *instruction {
+                Instruction::Jump(nnn) => format!("JP {}", self.address_operand(nnn as usize)),
+                Instruction::Call(nnn) => format!("CALL {}", self.address_operand(nnn as usize)),
+                Instruction::LoadI(nnn) => format!("LD I, {}", self.address_operand(nnn as usize)),
+                Instruction::JumpWithOffset(nnn) => {
+                    format!("JP V0, {}", self.address_operand(nnn as usize))
+                }
+                Instruction::LoadILong => format!(
+                    "LD I, LONG {}",
+                    self.address_operand(long_address.unwrap_or(0) as usize)
+                ),
+                _ => instruction.to_string(),
+            },
+            Item::Data { bytes, .. } => {
+                let bytes: Vec<String> =
+                    bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+                format!("db {}", bytes.join(", "))
+            }
+        }
+    }
+
+    fn raw(item: &Item) -> String {
+        match item {
+            Item::Code {
+                instruction,
+                long_address,
+                ..
+            } => match long_address {
+                Some(long_address) => format!("{:04X}{:04X}", instruction.encode(), long_address),
+                None => format!("{:04X}", instruction.encode()),
+            },
+            Item::Data { bytes, .. } => bytes.iter().map(|byte| format!("{:02X}", byte)).collect(),
+        }
+    }
+
+    // One line per item: address, raw bytes, mnemonic
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+        for item in &self.items {
+            if let Some(label) = self.labels.get(&item.address()) {
+                writeln!(listing, "{}:", label).unwrap();
+            }
+            writeln!(
+                listing,
+                "{:04X}  {:<16}  {}",
+                item.address(),
+                Disassembly::raw(item),
+                self.mnemonic(item)
+            )
+            .unwrap();
+        }
+        listing
+    }
+
+    // Same content as listing(), with address and raw bytes moved into comments
+    pub fn source(&self) -> String {
+        let mut source = String::new();
+        for item in &self.items {
+            if let Some(label) = self.labels.get(&item.address()) {
+                writeln!(source, "{}:", label).unwrap();
+            }
+            writeln!(
+                source,
+                "    {:<40} ; {:04X}  {}",
+                self.mnemonic(item),
+                item.address(),
+                Disassembly::raw(item)
+            )
+            .unwrap();
+        }
+        source
+    }
+}
+
+#[cfg(test)]
+#[path = "./disassembler_test.rs"]
+mod test;
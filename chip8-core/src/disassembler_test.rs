@@ -0,0 +1,57 @@
+use super::*;
+
+#[test]
+fn follows_control_flow_test() {
+    let rom = [
+        0x22, 0x06, // 200: CALL 206
+        0x12, 0x08, // 202: JP 208
+        0xF0, 0x90, // 204: sprite data
+        0xA2, 0x04, // 206: LD I, 204
+        0x00, 0xEE, // 208: RET
+    ];
+    let disassembly = Disassembly::new(&rom);
+    assert_eq!(disassembly.items.len(), 5);
+    assert_eq!(
+        disassembly.items[2],
+        Item::Data {
+            address: 0x204,
+            bytes: vec![0xF0, 0x90]
+        }
+    );
+    assert_eq!(disassembly.labels[&0x204], "data_204");
+    assert_eq!(disassembly.labels[&0x206], "sub_206");
+    assert_eq!(disassembly.labels[&0x208], "label_208");
+    assert_eq!(disassembly.mnemonic(&disassembly.items[0]), "CALL sub_206");
+    assert_eq!(
+        disassembly.mnemonic(&disassembly.items[3]),
+        "LD I, data_204"
+    );
+}
+#[test]
+fn skips_follow_both_paths_test() {
+    let rom = [
+        0x30, 0x01, // 200: SE V0, 0x01
+        0x00, 0xFD, // 202: EXIT
+        0x00, 0xFD, // 204: EXIT
+        0x12, 0x34, // 206: unreachable
+    ];
+    let disassembly = Disassembly::new(&rom);
+    assert!(matches!(
+        disassembly.items[2],
+        Item::Code { address: 0x204, .. }
+    ));
+    assert!(matches!(
+        disassembly.items[3],
+        Item::Data { address: 0x206, .. }
+    ));
+}
+#[test]
+fn long_load_test() {
+    let rom = [0xF0, 0x00, 0x02, 0x06, 0x00, 0xFD, 0xAA];
+    let disassembly = Disassembly::new(&rom);
+    assert_eq!(
+        disassembly.mnemonic(&disassembly.items[0]),
+        "LD I, LONG data_206"
+    );
+    assert!(disassembly.listing().contains("0200  F0000206"));
+}
@@ -0,0 +1,466 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::debugger::Debugger;
+use crate::processor::{Cpu, CpuError, StepOutcome};
+use crate::watchpoint::Access;
+use crate::{RAM, TIMER_RATE};
+
+// GDB remote serial protocol stub for a Cpu.
+// Registers are numbered V0-VF (0-15), I (16), PC (17), SP (18), DT (19) and ST (20),
+// multi byte registers are sent big endian like everything else on CHIP-8
+const REGISTER_COUNT: usize = 21;
+const PACKET_SIZE: usize = 0x1000;
+const INTERRUPT: u8 = 0x03;
+
+// Waits for one debugger to connect on 127.0.0.1:port and serves it until it detaches
+pub fn listen(cpu: &mut Cpu, port: u16, instructions_per_frame: usize) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let (stream, _) = listener.accept()?;
+    serve(cpu, stream, instructions_per_frame)
+}
+
+pub fn serve(cpu: &mut Cpu, stream: TcpStream, instructions_per_frame: usize) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut stub = GdbStub {
+        cpu,
+        debugger: Debugger::new(),
+        instructions_per_frame,
+        writer: stream.try_clone()?,
+        reader: BufReader::new(stream),
+        pending: VecDeque::new(),
+        stop_reply: "S05".to_string(),
+        hardware_breakpoints: HashSet::new(),
+        stop_reasons: false,
+    };
+    stub.run()
+}
+
+struct GdbStub<'a> {
+    cpu: &'a mut Cpu,
+    debugger: Debugger,
+    instructions_per_frame: usize,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    // bytes that arrived while the Cpu was running, other than ^C
+    pending: VecDeque<u8>,
+    // answer to '?', why the Cpu stopped last
+    stop_reply: String,
+    // breakpoints set with Z1, reported as hwbreak instead of swbreak
+    hardware_breakpoints: HashSet<usize>,
+    // the debugger understands swbreak and hwbreak in stop replies
+    stop_reasons: bool,
+}
+
+impl GdbStub<'_> {
+    fn run(&mut self) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            let reply = match packet.as_bytes().first() {
+                Some(b'?') => self.stop_reply.clone(),
+                Some(b'g') => self.read_registers(),
+                Some(b'G') => self.write_registers(&packet[1..]),
+                Some(b'p') => self.read_register(&packet[1..]),
+                Some(b'P') => self.write_register(&packet[1..]),
+                Some(b'm') => self.read_memory(&packet[1..]),
+                Some(b'M') => self.write_memory(&packet[1..]),
+                Some(b'c') => self.resume(&packet[1..], false)?,
+                Some(b's') => self.resume(&packet[1..], true)?,
+                Some(b'Z') => self.set_breakpoint(&packet[1..], true),
+                Some(b'z') => self.set_breakpoint(&packet[1..], false),
+                Some(b'q') => self.query(&packet[1..]),
+                Some(b'H') | Some(b'T') => "OK".to_string(),
+                Some(b'D') => {
+                    self.send_packet("OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => return Ok(()),
+                _ => String::new(),
+            };
+            self.send_packet(&reply)?;
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(byte) = self.pending.pop_front() {
+            return Ok(Some(byte));
+        }
+        let mut byte = [0];
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    // Next $packet#checksum with the checksum checked and acknowledged, None once the client is gone
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.read_byte()? {
+                None => return Ok(None),
+                Some(b'$') => {}
+                // acks and interrupts while already stopped
+                Some(_) => continue,
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+            let mut checksum = [0; 2];
+            for digit in &mut checksum {
+                *digit = self
+                    .read_byte()?
+                    .ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+            }
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(checksum_of(&data));
+            if valid {
+                self.writer.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+            self.writer.write_all(b"-")?;
+        }
+    }
+
+    fn send_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        loop {
+            self.writer.write_all(packet.as_bytes())?;
+            match self.read_byte()? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // Checks for a ^C from the client without blocking while the Cpu runs.
+    // Everything else that arrived is kept for read_packet
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.reader.get_ref().set_nonblocking(true)?;
+        let mut interrupted = false;
+        let mut buffer = [0; 64];
+        let result = loop {
+            match self.reader.read(&mut buffer) {
+                // the client is gone, stop running for it
+                Ok(0) => {
+                    interrupted = true;
+                    break Ok(());
+                }
+                Ok(count) => {
+                    for byte in &buffer[..count] {
+                        if *byte == INTERRUPT {
+                            interrupted = true;
+                        } else {
+                            self.pending.push_back(*byte);
+                        }
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        self.reader.get_ref().set_nonblocking(false)?;
+        result.map(|_| interrupted)
+    }
+
+    fn registers(&self) -> Vec<u8> {
+        let cpu = &self.cpu;
+        let mut registers = cpu.register.to_vec();
+        registers.extend_from_slice(&cpu.i.to_be_bytes());
+        registers.extend_from_slice(&(cpu.program_counter as u16).to_be_bytes());
+        registers.extend_from_slice(&[cpu.stack_pointer, cpu.delay_timer, cpu.sound_timer]);
+        registers
+    }
+
+    fn read_registers(&self) -> String {
+        to_hex(&self.registers())
+    }
+
+    fn write_registers(&mut self, data: &str) -> String {
+        let bytes = match from_hex(data) {
+            Some(bytes) if bytes.len() == self.registers().len() => bytes,
+            _ => return "E00".to_string(),
+        };
+        let mut offset = 0;
+        for register in 0..REGISTER_COUNT {
+            let size = register_size(register);
+            if !self.set_register(register, &bytes[offset..offset + size]) {
+                return "E00".to_string();
+            }
+            offset += size;
+        }
+        "OK".to_string()
+    }
+
+    fn read_register(&self, data: &str) -> String {
+        let register = match usize::from_str_radix(data, 16) {
+            Ok(register) if register < REGISTER_COUNT => register,
+            _ => return "E00".to_string(),
+        };
+        let offset: usize = (0..register).map(register_size).sum();
+        to_hex(&self.registers()[offset..offset + register_size(register)])
+    }
+
+    fn write_register(&mut self, data: &str) -> String {
+        let (register, value) = match data.split_once('=') {
+            Some(pair) => pair,
+            None => return "E00".to_string(),
+        };
+        let register = usize::from_str_radix(register, 16).ok();
+        match (register, from_hex(value)) {
+            (Some(register), Some(value))
+                if register < REGISTER_COUNT
+                    && value.len() == register_size(register)
+                    && self.set_register(register, &value) =>
+            {
+                "OK".to_string()
+            }
+            _ => "E00".to_string(),
+        }
+    }
+
+    // Value is big endian and already the size of the register
+    fn set_register(&mut self, register: usize, value: &[u8]) -> bool {
+        let cpu = &mut self.cpu;
+        match register {
+            0..=15 => cpu.register[register] = value[0],
+            16 => cpu.i = u16::from_be_bytes([value[0], value[1]]),
+            17 => cpu.program_counter = u16::from_be_bytes([value[0], value[1]]) as usize,
+            18 if value[0] as usize <= cpu.stack.len() => cpu.stack_pointer = value[0],
+            19 => cpu.delay_timer = value[0],
+            20 => cpu.sound_timer = value[0],
+            _ => return false,
+        }
+        true
+    }
+
+    fn read_memory(&self, data: &str) -> String {
+        match parse_range(data) {
+            Some((address, length)) => to_hex(&self.cpu.memory[address..address + length]),
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_memory(&mut self, data: &str) -> String {
+        let (range, bytes) = match data.split_once(':') {
+            Some(pair) => pair,
+            None => return "E01".to_string(),
+        };
+        match (parse_range(range), from_hex(bytes)) {
+            (Some((address, length)), Some(bytes)) if bytes.len() == length => {
+                self.cpu.memory[address..address + length].copy_from_slice(&bytes);
+                "OK".to_string()
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    // c and s, both can take the address to resume at
+    fn resume(&mut self, data: &str, step: bool) -> io::Result<String> {
+        if let Ok(address) = usize::from_str_radix(data, 16) {
+            self.cpu.program_counter = address;
+        }
+        self.debugger.watch_hit = None;
+        let result = if step {
+            self.debugger.step(self.cpu)
+        } else {
+            self.debugger.resume();
+            // at the speed of the emulator, not as fast as the host can go
+            let frame = Duration::from_micros(TIMER_RATE);
+            let mut next_frame = Instant::now();
+            loop {
+                next_frame += frame;
+                let result = self
+                    .debugger
+                    .run_frame(self.cpu, self.instructions_per_frame);
+                let stopped = match result {
+                    Ok(StepOutcome::Executed) | Ok(StepOutcome::WaitingForKey) => {
+                        self.debugger.paused
+                    }
+                    _ => true,
+                };
+                if stopped {
+                    break result;
+                }
+                if self.interrupted()? {
+                    self.debugger.pause();
+                    self.stop_reply = "S02".to_string();
+                    return Ok(self.stop_reply.clone());
+                }
+                sleep(next_frame.saturating_duration_since(Instant::now()));
+            }
+        };
+        let program_counter = self.cpu.program_counter;
+        self.stop_reply = match result {
+            Ok(StepOutcome::Halted) => "W00".to_string(),
+            Ok(StepOutcome::Watchpoint(hit)) => match hit.access {
+                Access::Read => format!("T05rwatch:{:x};", hit.address),
+                Access::Write => format!("T05watch:{:x};", hit.address),
+                Access::Execute if self.stop_reasons => "T05hwbreak:;".to_string(),
+                Access::Execute => "S05".to_string(),
+            },
+            Ok(_) if !step && self.stop_reasons && self.debugger.is_breakpoint(program_counter) => {
+                if self.hardware_breakpoints.contains(&program_counter) {
+                    "T05hwbreak:;".to_string()
+                } else {
+                    "T05swbreak:;".to_string()
+                }
+            }
+            Ok(_) => "S05".to_string(),
+            Err(CpuError::MemoryOutOfRange { .. })
+            | Err(CpuError::ProgramCounterOutOfRange { .. }) => "S0b".to_string(),
+            Err(_) => "S04".to_string(),
+        };
+        Ok(self.stop_reply.clone())
+    }
+
+    // Z/z type,address,kind: 0 and 1 are breakpoints, 2 write, 3 read and 4 access watchpoints
+    fn set_breakpoint(&mut self, data: &str, insert: bool) -> String {
+        let mut fields = data.split(',');
+        let kind = fields.next();
+        let address = fields
+            .next()
+            .and_then(|address| usize::from_str_radix(address, 16).ok());
+        let length = fields
+            .next()
+            .and_then(|length| usize::from_str_radix(length, 16).ok());
+        let (kind, address, length) = match (kind, address, length) {
+            (Some(kind), Some(address), Some(length)) if address < RAM => (kind, address, length),
+            _ => return "E00".to_string(),
+        };
+        let accesses: &[Access] = match kind {
+            "0" | "1" => {
+                if insert {
+                    self.debugger.add_breakpoint(address);
+                } else {
+                    self.debugger.remove_breakpoint(address);
+                }
+                if kind == "1" && insert {
+                    self.hardware_breakpoints.insert(address);
+                } else {
+                    self.hardware_breakpoints.remove(&address);
+                }
+                return "OK".to_string();
+            }
+            "2" => &[Access::Write],
+            "3" => &[Access::Read],
+            "4" => &[Access::Read, Access::Write],
+            _ => return String::new(),
+        };
+        let range = address..(address + length.max(1)).min(RAM);
+        for access in accesses {
+            if insert {
+                self.cpu.add_watchpoint(range.clone(), *access);
+            } else {
+                self.cpu.remove_watchpoint(range.clone(), *access);
+            }
+        }
+        "OK".to_string()
+    }
+
+    fn query(&mut self, data: &str) -> String {
+        if let Some(features) = data.strip_prefix("Supported") {
+            self.stop_reasons = features.contains("swbreak+") && features.contains("hwbreak+");
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+",
+                PACKET_SIZE
+            );
+        }
+        if let Some(request) = data.strip_prefix("Xfer:features:read:target.xml:") {
+            return match parse_range_unchecked(request) {
+                Some((offset, length)) => {
+                    let description = target_description();
+                    let start = offset.min(description.len());
+                    let end = (start + length).min(description.len());
+                    let more = if end < description.len() { 'm' } else { 'l' };
+                    format!("{}{}", more, &description[start..end])
+                }
+                None => "E00".to_string(),
+            };
+        }
+        match data {
+            "Attached" => "1".to_string(),
+            "C" => "QC1".to_string(),
+            "fThreadInfo" => "m1".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn register_size(register: usize) -> usize {
+    match register {
+        16 | 17 => 2,
+        _ => 1,
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(data: &str) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(data.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_range_unchecked(data: &str) -> Option<(usize, usize)> {
+    let (address, length) = data.split_once(',')?;
+    let address = usize::from_str_radix(address, 16).ok()?;
+    let length = usize::from_str_radix(length, 16).ok()?;
+    Some((address, length))
+}
+
+// address,length of a memory access, only when it fits in RAM
+fn parse_range(data: &str) -> Option<(usize, usize)> {
+    let (address, length) = parse_range_unchecked(data)?;
+    if address.checked_add(length)? > RAM {
+        return None;
+    }
+    Some((address, length))
+}
+
+fn target_description() -> String {
+    let mut registers = String::new();
+    for register in 0..16 {
+        registers.push_str(&format!(
+            "<reg name=\"v{:x}\" bitsize=\"8\" type=\"uint8\"/>",
+            register
+        ));
+    }
+    registers.push_str("<reg name=\"i\" bitsize=\"16\" type=\"data_ptr\"/>");
+    registers.push_str("<reg name=\"pc\" bitsize=\"16\" type=\"code_ptr\"/>");
+    for name in ["sp", "dt", "st"] {
+        registers.push_str(&format!(
+            "<reg name=\"{}\" bitsize=\"8\" type=\"uint8\"/>",
+            name
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+         <target version=\"1.0\"><architecture>chip8</architecture>\
+         <feature name=\"org.chip8.core\">{}</feature></target>",
+        registers
+    )
+}
+
+#[cfg(test)]
+#[path = "./gdb_test.rs"]
+mod test;
@@ -0,0 +1,160 @@
+use super::*;
+use crate::quirks::Quirks;
+use std::thread;
+
+// 0x200: I := 0x300, V0 += 1, save V0, jump 0x202
+const ROM: [u8; 8] = [0xA3, 0x00, 0x70, 0x01, 0xF0, 0x55, 0x12, 0x02];
+
+fn connect() -> (TcpStream, thread::JoinHandle<Cpu>) {
+    connect_with(|_| {})
+}
+
+// set_up runs on the Cpu before the stub serves it, for what gdb cannot ask for
+fn connect_with(set_up: fn(&mut Cpu)) -> (TcpStream, thread::JoinHandle<Cpu>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut cpu = Cpu::new(Quirks::default());
+        cpu.load_rom(&ROM);
+        set_up(&mut cpu);
+        let (stream, _) = listener.accept().unwrap();
+        serve(&mut cpu, stream, 9).unwrap();
+        cpu
+    });
+    (TcpStream::connect(address).unwrap(), server)
+}
+
+fn send(client: &mut TcpStream, command: &str) {
+    let packet = format!("${}#{:02x}", command, checksum_of(command.as_bytes()));
+    client.write_all(packet.as_bytes()).unwrap();
+    let mut ack = [0];
+    client.read_exact(&mut ack).unwrap();
+    assert_eq!(ack[0], b'+');
+}
+
+fn reply(client: &mut TcpStream) -> String {
+    let mut reply = Vec::new();
+    let mut byte = [0];
+    while byte[0] != b'#' {
+        client.read_exact(&mut byte).unwrap();
+        reply.push(byte[0]);
+    }
+    let mut checksum = [0; 2];
+    client.read_exact(&mut checksum).unwrap();
+    client.write_all(b"+").unwrap();
+    let reply = String::from_utf8(reply).unwrap();
+    reply[1..reply.len() - 1].to_string()
+}
+
+// Plays the debugger side of a session and returns the Cpu once the stub is done with it
+fn session(script: &[(&str, &str)]) -> Cpu {
+    session_with(|_| {}, script)
+}
+
+fn session_with(set_up: fn(&mut Cpu), script: &[(&str, &str)]) -> Cpu {
+    let (mut client, server) = connect_with(set_up);
+    for (command, expected) in script {
+        send(&mut client, command);
+        if *command == "k" {
+            break;
+        }
+        assert_eq!(reply(&mut client), *expected, "reply to {}", command);
+    }
+    server.join().unwrap()
+}
+
+#[test]
+fn registers_and_memory_test() {
+    let cpu = session(&[
+        (
+            "qSupported:multiprocess+",
+            "PacketSize=1000;qXfer:features:read+;swbreak+;hwbreak+",
+        ),
+        ("?", "S05"),
+        ("m200,4", "a3007001"),
+        ("M300,2:abcd", "OK"),
+        ("m300,2", "abcd"),
+        ("mffff,2", "E01"),
+        ("P0=2a", "OK"),
+        ("p0", "2a"),
+        ("p11", "0200"),
+        ("g", "2a00000000000000000000000000000000000200000000"),
+        ("D", "OK"),
+    ]);
+    assert_eq!(cpu.registers()[0], 0x2A);
+    assert_eq!(cpu.memory()[0x300], 0xAB);
+}
+
+#[test]
+fn step_breakpoint_and_watchpoint_test() {
+    let cpu = session(&[
+        ("s", "S05"),
+        ("p10", "0300"),
+        ("Z0,206,2", "OK"),
+        ("c", "S05"),
+        ("p11", "0206"),
+        ("z0,206,2", "OK"),
+        ("Z2,300,1", "OK"),
+        ("c", "T05watch:300;"),
+        ("p11", "0206"),
+        ("z2,300,1", "OK"),
+        ("k", ""),
+    ]);
+    assert_eq!(cpu.registers()[0], 2);
+    assert!(cpu.watchpoints().is_empty());
+}
+
+#[test]
+fn stop_reasons_test() {
+    let supported = (
+        "qSupported:multiprocess+;swbreak+;hwbreak+",
+        "PacketSize=1000;qXfer:features:read+;swbreak+;hwbreak+",
+    );
+    session(&[
+        supported,
+        ("Z0,204,2", "OK"),
+        ("c", "T05swbreak:;"),
+        ("?", "T05swbreak:;"),
+        ("z0,204,2", "OK"),
+        ("Z1,206,2", "OK"),
+        ("c", "T05hwbreak:;"),
+        ("p11", "0206"),
+        ("z1,206,2", "OK"),
+        ("s", "S05"),
+        ("k", ""),
+    ]);
+    session_with(
+        |cpu| cpu.add_watchpoint(0x202..0x204, Access::Execute),
+        &[supported, ("c", "T05hwbreak:;"), ("p11", "0204"), ("k", "")],
+    );
+    // a debugger that does not know the stop reasons only hears the signal
+    session_with(
+        |cpu| cpu.add_watchpoint(0x202..0x204, Access::Execute),
+        &[("Z0,204,2", "OK"), ("c", "S05"), ("c", "S05"), ("k", "")],
+    );
+}
+
+#[test]
+fn target_description_test() {
+    let (mut client, server) = connect();
+    send(&mut client, "qXfer:features:read:target.xml:0,fff");
+    let description = reply(&mut client);
+    assert!(description.starts_with('l'));
+    assert!(description.contains("<architecture>chip8</architecture>"));
+    send(&mut client, "k");
+    server.join().unwrap();
+}
+
+#[test]
+fn interrupt_test() {
+    let (mut client, server) = connect();
+    send(&mut client, "c");
+    // a stray ack in front of the ^C must not hide it
+    client.write_all(b"+").unwrap();
+    client.write_all(&[INTERRUPT]).unwrap();
+    assert_eq!(reply(&mut client), "S02");
+    send(&mut client, "?");
+    assert_eq!(reply(&mut client), "S02");
+    send(&mut client, "k");
+    assert!(server.join().unwrap().registers()[0] > 0);
+}
@@ -0,0 +1,117 @@
+use std::ops::RangeInclusive;
+
+use crate::debugger::Debugger;
+use crate::instruction::Instruction;
+use crate::processor::{Cpu, CpuError, StepOutcome};
+
+// Keys held down during a range of frames, written as `<frame>[-<last frame>]:<keys>`
+// with the keys as hex digits, e.g. `30-40:5A` holds 5 and A from frame 30 to frame 40
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPress {
+    pub frames: RangeInclusive<usize>,
+    pub keys: Vec<u8>,
+}
+
+impl KeyPress {
+    pub fn parse(text: &str) -> Option<KeyPress> {
+        let (frames, keys) = text.split_once(':')?;
+        let frames = match frames.split_once('-') {
+            Some((first, last)) => first.parse().ok()?..=last.parse().ok()?,
+            None => {
+                let frame = frames.parse().ok()?;
+                frame..=frame
+            }
+        };
+        let keys = keys
+            .chars()
+            .map(|key| key.to_digit(16).map(|key| key as u8))
+            .collect::<Option<Vec<u8>>>()?;
+        Some(KeyPress { frames, keys })
+    }
+}
+
+// Why a headless run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunEnd {
+    Frames,
+    ProgramCounter,
+    // the program reached a jump to itself, how most test ROMs finish
+    Loop,
+    Halted,
+}
+
+// Runs a Cpu frame by frame without a window, for regression tests on machines without a display
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadlessRun {
+    pub frames: usize,
+    pub instructions_per_frame: usize,
+    pub until_program_counter: Option<usize>,
+    pub until_loop: bool,
+    pub keys: Vec<KeyPress>,
+}
+
+impl HeadlessRun {
+    pub fn new(frames: usize, instructions_per_frame: usize) -> HeadlessRun {
+        HeadlessRun {
+            frames,
+            instructions_per_frame,
+            until_program_counter: None,
+            until_loop: false,
+            keys: Vec::new(),
+        }
+    }
+
+    fn key_map(&self, frame: usize) -> [bool; 16] {
+        let mut key_map = [false; 16];
+        for press in self
+            .keys
+            .iter()
+            .filter(|press| press.frames.contains(&frame))
+        {
+            for key in &press.keys {
+                key_map[*key as usize] = true;
+            }
+        }
+        key_map
+    }
+
+    fn should_stop(&self, cpu: &Cpu) -> Option<RunEnd> {
+        let program_counter = cpu.program_counter();
+        if self.until_program_counter == Some(program_counter) {
+            return Some(RunEnd::ProgramCounter);
+        }
+        if self.until_loop {
+            if let Some((Instruction::Jump(address), _)) =
+                Debugger::instruction_at(cpu, program_counter)
+            {
+                if address as usize == program_counter {
+                    return Some(RunEnd::Loop);
+                }
+            }
+        }
+        None
+    }
+
+    // The frames that were run and why the run ended
+    pub fn run(&self, cpu: &mut Cpu) -> Result<(usize, RunEnd), CpuError> {
+        for frame in 0..self.frames {
+            cpu.load_key_map(&self.key_map(frame));
+            for _ in 0..self.instructions_per_frame {
+                if let Some(end) = self.should_stop(cpu) {
+                    return Ok((frame, end));
+                }
+                match cpu.run_next_instruction()? {
+                    StepOutcome::Halted => return Ok((frame + 1, RunEnd::Halted)),
+                    StepOutcome::WaitingForKey => break,
+                    _ => {}
+                }
+            }
+            cpu.tick_timers();
+        }
+        Ok((self.frames, RunEnd::Frames))
+    }
+}
+
+#[cfg(test)]
+#[path = "./headless_test.rs"]
+mod test;
@@ -0,0 +1,47 @@
+use super::*;
+use crate::quirks::Quirks;
+
+// 0x200: V0 := 5, skip if key 5 is down, jump 0x202, jump 0x206 (the end)
+const ROM: [u8; 8] = [0x60, 0x05, 0xE0, 0x9E, 0x12, 0x02, 0x12, 0x06];
+
+fn cpu() -> Cpu {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    cpu
+}
+
+#[test]
+fn key_press_parse_test() {
+    assert_eq!(
+        KeyPress::parse("30-40:5a"),
+        Some(KeyPress {
+            frames: 30..=40,
+            keys: vec![5, 0xA]
+        })
+    );
+    assert_eq!(
+        KeyPress::parse("7:F"),
+        Some(KeyPress {
+            frames: 7..=7,
+            keys: vec![0xF]
+        })
+    );
+    assert_eq!(KeyPress::parse("7"), None);
+    assert_eq!(KeyPress::parse("7:G"), None);
+}
+
+#[test]
+fn run_test() {
+    let mut run = HeadlessRun::new(10, 9);
+    assert_eq!(run.run(&mut cpu()), Ok((10, RunEnd::Frames)));
+
+    run.until_loop = true;
+    run.keys.push(KeyPress::parse("3:5").unwrap());
+    let mut cpu = self::cpu();
+    assert_eq!(run.run(&mut cpu), Ok((3, RunEnd::Loop)));
+    assert_eq!(cpu.program_counter(), 0x206);
+
+    run.until_loop = false;
+    run.until_program_counter = Some(0x206);
+    assert_eq!(run.run(&mut self::cpu()), Ok((3, RunEnd::ProgramCounter)));
+}
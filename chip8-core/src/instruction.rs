@@ -0,0 +1,257 @@
+use std::fmt;
+
+// One decoded opcode. x and y are register indexes, nn a byte, n a nibble, nnn an address.
+// F000 NNNN is four bytes long, only its first word is part of LoadILong,
+// whoever executes or prints it reads the address from the following word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    ScrollDown(u8),                  // 00CN
+    ScrollUp(u8),                    // 00DN
+    ClearScreen,                     // 00E0
+    Return,                          // 00EE
+    ScrollRight,                     // 00FB
+    ScrollLeft,                      // 00FC
+    Exit,                            // 00FD
+    LowRes,                          // 00FE
+    HighRes,                         // 00FF
+    Jump(u16),                       // 1NNN
+    Call(u16),                       // 2NNN
+    SkipIfEqual(u8, u8),             // 3XNN
+    SkipIfNotEqual(u8, u8),          // 4XNN
+    SkipIfRegistersEqual(u8, u8),    // 5XY0
+    SaveRange(u8, u8),               // 5XY2
+    LoadRange(u8, u8),               // 5XY3
+    Store(u8, u8),                   // 6XNN
+    Add(u8, u8),                     // 7XNN
+    Copy(u8, u8),                    // 8XY0
+    Or(u8, u8),                      // 8XY1
+    And(u8, u8),                     // 8XY2
+    Xor(u8, u8),                     // 8XY3
+    AddRegister(u8, u8),             // 8XY4
+    Sub(u8, u8),                     // 8XY5
+    ShiftRight(u8, u8),              // 8XY6
+    SubReverse(u8, u8),              // 8XY7
+    ShiftLeft(u8, u8),               // 8XYE
+    SkipIfRegistersNotEqual(u8, u8), // 9XY0
+    LoadI(u16),                      // ANNN
+    JumpWithOffset(u16),             // BNNN
+    Random(u8, u8),                  // CXNN
+    Draw(u8, u8, u8),                // DXYN
+    SkipIfPressed(u8),               // EX9E
+    SkipIfNotPressed(u8),            // EXA1
+    LoadILong,                       // F000 NNNN
+    SelectPlane(u8),                 // FN01
+    LoadAudioPattern,                // F002
+    StoreDelay(u8),                  // FX07
+    WaitForKey(u8),                  // FX0A
+    SetDelay(u8),                    // FX15
+    SetSound(u8),                    // FX18
+    AddToI(u8),                      // FX1E
+    LoadFont(u8),                    // FX29
+    LoadBigFont(u8),                 // FX30
+    StoreBcd(u8),                    // FX33
+    SetPitch(u8),                    // FX3A
+    SaveRegisters(u8),               // FX55
+    LoadRegisters(u8),               // FX65
+    SaveFlags(u8),                   // FX75
+    LoadFlags(u8),                   // FX85
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        let c = ((opcode & 0xF000) >> 12) as u8;
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+        let d = (opcode & 0x000F) as u8;
+
+        let nnn = opcode & 0x0FFF;
+        let kk = (opcode & 0x00FF) as u8;
+
+        let instruction = match (c, x, y, d) {
+            (0x0, 0x0, 0xC, _) => Instruction::ScrollDown(d),
+            (0x0, 0x0, 0xD, _) => Instruction::ScrollUp(d),
+            (0x0, 0x0, 0xE, 0x0) => Instruction::ClearScreen,
+            (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+            (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+            (0x0, 0x0, 0xF, 0xE) => Instruction::LowRes,
+            (0x0, 0x0, 0xF, 0xF) => Instruction::HighRes,
+            (0x1, _, _, _) => Instruction::Jump(nnn),
+            (0x2, _, _, _) => Instruction::Call(nnn),
+            (0x3, _, _, _) => Instruction::SkipIfEqual(x, kk),
+            (0x4, _, _, _) => Instruction::SkipIfNotEqual(x, kk),
+            (0x5, _, _, 0x0) => Instruction::SkipIfRegistersEqual(x, y),
+            (0x5, _, _, 0x2) => Instruction::SaveRange(x, y),
+            (0x5, _, _, 0x3) => Instruction::LoadRange(x, y),
+            (0x6, _, _, _) => Instruction::Store(x, kk),
+            (0x7, _, _, _) => Instruction::Add(x, kk),
+            (0x8, _, _, 0x0) => Instruction::Copy(x, y),
+            (0x8, _, _, 0x1) => Instruction::Or(x, y),
+            (0x8, _, _, 0x2) => Instruction::And(x, y),
+            (0x8, _, _, 0x3) => Instruction::Xor(x, y),
+            (0x8, _, _, 0x4) => Instruction::AddRegister(x, y),
+            (0x8, _, _, 0x5) => Instruction::Sub(x, y),
+            (0x8, _, _, 0x6) => Instruction::ShiftRight(x, y),
+            (0x8, _, _, 0x7) => Instruction::SubReverse(x, y),
+            (0x8, _, _, 0xE) => Instruction::ShiftLeft(x, y),
+            (0x9, _, _, 0x0) => Instruction::SkipIfRegistersNotEqual(x, y),
+            (0xA, _, _, _) => Instruction::LoadI(nnn),
+            (0xB, _, _, _) => Instruction::JumpWithOffset(nnn),
+            (0xC, _, _, _) => Instruction::Random(x, kk),
+            (0xD, _, _, _) => Instruction::Draw(x, y, d),
+            (0xE, _, 0x9, 0xE) => Instruction::SkipIfPressed(x),
+            (0xE, _, 0xA, 0x1) => Instruction::SkipIfNotPressed(x),
+            (0xF, 0x0, 0x0, 0x0) => Instruction::LoadILong,
+            (0xF, _, 0x0, 0x1) => Instruction::SelectPlane(x),
+            (0xF, 0x0, 0x0, 0x2) => Instruction::LoadAudioPattern,
+            (0xF, _, 0x0, 0x7) => Instruction::StoreDelay(x),
+            (0xF, _, 0x0, 0xA) => Instruction::WaitForKey(x),
+            (0xF, _, 0x1, 0x5) => Instruction::SetDelay(x),
+            (0xF, _, 0x1, 0x8) => Instruction::SetSound(x),
+            (0xF, _, 0x1, 0xE) => Instruction::AddToI(x),
+            (0xF, _, 0x2, 0x9) => Instruction::LoadFont(x),
+            (0xF, _, 0x3, 0x0) => Instruction::LoadBigFont(x),
+            (0xF, _, 0x3, 0x3) => Instruction::StoreBcd(x),
+            (0xF, _, 0x3, 0xA) => Instruction::SetPitch(x),
+            (0xF, _, 0x5, 0x5) => Instruction::SaveRegisters(x),
+            (0xF, _, 0x6, 0x5) => Instruction::LoadRegisters(x),
+            (0xF, _, 0x7, 0x5) => Instruction::SaveFlags(x),
+            (0xF, _, 0x8, 0x5) => Instruction::LoadFlags(x),
+            _ => return None,
+        };
+        Some(instruction)
+    }
+
+    // Operands too wide for their field are cut down to it instead of spilling into the opcode
+    pub fn encode(&self) -> u16 {
+        let n = |n: u8| (n & 0xF) as u16;
+        let nnn = |nnn: u16| nnn & 0xFFF;
+        let xy = |c: u16, x: u8, y: u8, d: u16| c << 12 | n(x) << 8 | n(y) << 4 | d;
+        let xkk = |c: u16, x: u8, kk: u8| c << 12 | n(x) << 8 | kk as u16;
+        let fx = |x: u8, kk: u16| 0xF000 | n(x) << 8 | kk;
+
+        match *self {
+            Instruction::ScrollDown(lines) => 0x00C0 | n(lines),
+            Instruction::ScrollUp(lines) => 0x00D0 | n(lines),
+            Instruction::ClearScreen => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::LowRes => 0x00FE,
+            Instruction::HighRes => 0x00FF,
+            Instruction::Jump(address) => 0x1000 | nnn(address),
+            Instruction::Call(address) => 0x2000 | nnn(address),
+            Instruction::SkipIfEqual(x, kk) => xkk(0x3, x, kk),
+            Instruction::SkipIfNotEqual(x, kk) => xkk(0x4, x, kk),
+            Instruction::SkipIfRegistersEqual(x, y) => xy(0x5, x, y, 0x0),
+            Instruction::SaveRange(x, y) => xy(0x5, x, y, 0x2),
+            Instruction::LoadRange(x, y) => xy(0x5, x, y, 0x3),
+            Instruction::Store(x, kk) => xkk(0x6, x, kk),
+            Instruction::Add(x, kk) => xkk(0x7, x, kk),
+            Instruction::Copy(x, y) => xy(0x8, x, y, 0x0),
+            Instruction::Or(x, y) => xy(0x8, x, y, 0x1),
+            Instruction::And(x, y) => xy(0x8, x, y, 0x2),
+            Instruction::Xor(x, y) => xy(0x8, x, y, 0x3),
+            Instruction::AddRegister(x, y) => xy(0x8, x, y, 0x4),
+            Instruction::Sub(x, y) => xy(0x8, x, y, 0x5),
+            Instruction::ShiftRight(x, y) => xy(0x8, x, y, 0x6),
+            Instruction::SubReverse(x, y) => xy(0x8, x, y, 0x7),
+            Instruction::ShiftLeft(x, y) => xy(0x8, x, y, 0xE),
+            Instruction::SkipIfRegistersNotEqual(x, y) => xy(0x9, x, y, 0x0),
+            Instruction::LoadI(address) => 0xA000 | nnn(address),
+            Instruction::JumpWithOffset(address) => 0xB000 | nnn(address),
+            Instruction::Random(x, kk) => xkk(0xC, x, kk),
+            Instruction::Draw(x, y, rows) => xy(0xD, x, y, n(rows)),
+            Instruction::SkipIfPressed(x) => xkk(0xE, x, 0x9E),
+            Instruction::SkipIfNotPressed(x) => xkk(0xE, x, 0xA1),
+            Instruction::LoadILong => 0xF000,
+            Instruction::SelectPlane(x) => fx(x, 0x01),
+            Instruction::LoadAudioPattern => 0xF002,
+            Instruction::StoreDelay(x) => fx(x, 0x07),
+            Instruction::WaitForKey(x) => fx(x, 0x0A),
+            Instruction::SetDelay(x) => fx(x, 0x15),
+            Instruction::SetSound(x) => fx(x, 0x18),
+            Instruction::AddToI(x) => fx(x, 0x1E),
+            Instruction::LoadFont(x) => fx(x, 0x29),
+            Instruction::LoadBigFont(x) => fx(x, 0x30),
+            Instruction::StoreBcd(x) => fx(x, 0x33),
+            Instruction::SetPitch(x) => fx(x, 0x3A),
+            Instruction::SaveRegisters(x) => fx(x, 0x55),
+            Instruction::LoadRegisters(x) => fx(x, 0x65),
+            Instruction::SaveFlags(x) => fx(x, 0x75),
+            Instruction::LoadFlags(x) => fx(x, 0x85),
+        }
+    }
+
+    // Size in bytes, including the address word of F000 NNNN
+    pub fn size(&self) -> usize {
+        match self {
+            Instruction::LoadILong => 4,
+            _ => 2,
+        }
+    }
+}
+
+// Mnemonics follow Cowgod's CHIP-8 reference, extended with the usual SCHIP and XO-CHIP names
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::ScrollDown(n) => write!(f, "SCD {}", n),
+            Instruction::ScrollUp(n) => write!(f, "SCU {}", n),
+            Instruction::ClearScreen => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::LowRes => write!(f, "LOW"),
+            Instruction::HighRes => write!(f, "HIGH"),
+            Instruction::Jump(nnn) => write!(f, "JP 0x{:03X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL 0x{:03X}", nnn),
+            Instruction::SkipIfEqual(x, kk) => write!(f, "SE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipIfNotEqual(x, kk) => write!(f, "SNE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipIfRegistersEqual(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::SaveRange(x, y) => write!(f, "SAVE V{:X}, V{:X}", x, y),
+            Instruction::LoadRange(x, y) => write!(f, "LOAD V{:X}, V{:X}", x, y),
+            Instruction::Store(x, kk) => write!(f, "LD V{:X}, 0x{:02X}", x, kk),
+            Instruction::Add(x, kk) => write!(f, "ADD V{:X}, 0x{:02X}", x, kk),
+            Instruction::Copy(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddRegister(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubReverse(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipIfRegistersNotEqual(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(nnn) => write!(f, "LD I, 0x{:03X}", nnn),
+            Instruction::JumpWithOffset(nnn) => write!(f, "JP V0, 0x{:03X}", nnn),
+            Instruction::Random(x, kk) => write!(f, "RND V{:X}, 0x{:02X}", x, kk),
+            Instruction::Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipIfPressed(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipIfNotPressed(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::LoadILong => write!(f, "LD I, LONG"),
+            Instruction::SelectPlane(x) => write!(f, "PLANE {}", x),
+            Instruction::LoadAudioPattern => write!(f, "AUDIO"),
+            Instruction::StoreDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitForKey(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddToI(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::LoadFont(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::LoadBigFont(x) => write!(f, "LD HF, V{:X}", x),
+            Instruction::StoreBcd(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::SetPitch(x) => write!(f, "PITCH V{:X}", x),
+            Instruction::SaveRegisters(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegisters(x) => write!(f, "LD V{:X}, [I]", x),
+            Instruction::SaveFlags(x) => write!(f, "LD R, V{:X}", x),
+            Instruction::LoadFlags(x) => write!(f, "LD V{:X}, R", x),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./instruction_test.rs"]
+mod test;
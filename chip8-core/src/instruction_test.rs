@@ -0,0 +1,83 @@
+use super::*;
+
+#[test]
+fn decode_encode_round_trip_test() {
+    for opcode in 0..=u16::MAX {
+        if let Some(instruction) = Instruction::decode(opcode) {
+            assert_eq!(instruction.encode(), opcode);
+        }
+    }
+}
+
+#[test]
+fn encode_boundaries_test() {
+    let boundaries = [
+        Instruction::Jump(0x000),
+        Instruction::Jump(0xFFF),
+        Instruction::Call(0xFFF),
+        Instruction::LoadI(0xFFF),
+        Instruction::JumpWithOffset(0xFFF),
+        Instruction::ScrollDown(0xF),
+        Instruction::ScrollUp(0x0),
+        Instruction::Store(0xF, 0xFF),
+        Instruction::Copy(0xF, 0xF),
+        Instruction::Draw(0xF, 0xF, 0xF),
+        Instruction::LoadFont(0xF),
+    ];
+    for instruction in boundaries {
+        assert_eq!(Instruction::decode(instruction.encode()), Some(instruction));
+    }
+    // one past the boundary is cut down to the field and does not change the opcode
+    let too_wide = [
+        (Instruction::Jump(0x1FFF), Instruction::Jump(0xFFF)),
+        (Instruction::Call(0xF000), Instruction::Call(0x000)),
+        (Instruction::LoadI(0x1000), Instruction::LoadI(0x000)),
+        (Instruction::ScrollDown(0x1F), Instruction::ScrollDown(0xF)),
+        (Instruction::ScrollUp(0x10), Instruction::ScrollUp(0x0)),
+        (
+            Instruction::Store(0x1F, 0xFF),
+            Instruction::Store(0xF, 0xFF),
+        ),
+        (Instruction::Copy(0x10, 0xFF), Instruction::Copy(0x0, 0xF)),
+        (
+            Instruction::Draw(0x10, 0x20, 0x1F),
+            Instruction::Draw(0x0, 0x0, 0xF),
+        ),
+        (Instruction::LoadFont(0xFF), Instruction::LoadFont(0xF)),
+    ];
+    for (instruction, masked) in too_wide {
+        assert_eq!(Instruction::decode(instruction.encode()), Some(masked));
+    }
+}
+
+#[test]
+fn decode_test() {
+    assert_eq!(Instruction::decode(0x00E0), Some(Instruction::ClearScreen));
+    assert_eq!(Instruction::decode(0x2400), Some(Instruction::Call(0x400)));
+    assert_eq!(
+        Instruction::decode(0x8AB6),
+        Some(Instruction::ShiftRight(0xA, 0xB))
+    );
+    assert_eq!(
+        Instruction::decode(0xD125),
+        Some(Instruction::Draw(1, 2, 5))
+    );
+    assert_eq!(Instruction::decode(0xF000), Some(Instruction::LoadILong));
+    assert_eq!(Instruction::decode(0x0123), None);
+    assert_eq!(Instruction::decode(0x5121), None);
+    assert_eq!(Instruction::decode(0xFFFF), None);
+}
+#[test]
+fn size_test() {
+    assert_eq!(Instruction::LoadILong.size(), 4);
+    assert_eq!(Instruction::Jump(0x200).size(), 2);
+}
+#[test]
+fn display_test() {
+    assert_eq!(Instruction::ClearScreen.to_string(), "CLS");
+    assert_eq!(Instruction::Jump(0x2A0).to_string(), "JP 0x2A0");
+    assert_eq!(Instruction::Store(0xC, 0x0F).to_string(), "LD VC, 0x0F");
+    assert_eq!(Instruction::Draw(0, 1, 15).to_string(), "DRW V0, V1, 15");
+    assert_eq!(Instruction::SaveRegisters(3).to_string(), "LD [I], V3");
+    assert_eq!(Instruction::LoadRange(2, 5).to_string(), "LOAD V2, V5");
+}
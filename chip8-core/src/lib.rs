@@ -0,0 +1,28 @@
+pub mod assembler;
+pub mod cartridge_reader;
+pub mod dap;
+pub mod debugger;
+pub mod disassembler;
+pub mod gdb;
+pub mod headless;
+pub mod instruction;
+pub mod movie;
+pub mod octo;
+pub mod processor;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+pub mod save_state;
+pub mod screen_dump;
+pub mod symbols;
+pub mod trace;
+pub mod trace_diff;
+pub mod watchpoint;
+
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+pub const RAM: usize = 65536;
+pub const PROGRAM_START: usize = 0x200;
+pub const TIMER_RATE: u64 = 16666; // 60 Hz
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::processor::{Cpu, CpuError, StepOutcome};
+use crate::quirks::Quirks;
+use crate::random::Random;
+use crate::save_state::rom_hash;
+
+// Layout of a movie file, numbers big endian:
+//   "C8MV", version, SHA-1 of the ROM (20 bytes), random seed (u64), quirks (one bit each),
+//   instructions per frame (u16), then one u16 per frame with bit N set while key N is held
+const MAGIC: &[u8; 4] = b"C8MV";
+pub const MOVIE_VERSION: u8 = 1;
+const HEADER_LEN: usize = 36;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovieError {
+    NotAMovie,
+    UnsupportedVersion(u8),
+    Corrupted,
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovieError::NotAMovie => write!(f, "not a movie file"),
+            MovieError::UnsupportedVersion(version) => {
+                write!(f, "unsupported movie version {}", version)
+            }
+            MovieError::Corrupted => write!(f, "movie file is corrupted"),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+// Everything needed to replay a session exactly: the keys held on every frame
+// plus the settings the Cpu was started with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: [u8; 20],
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub instructions_per_frame: u16,
+    frames: Vec<u16>,
+}
+
+impl Movie {
+    pub fn new(rom: &[u8], seed: u64, quirks: Quirks, instructions_per_frame: u16) -> Movie {
+        Movie {
+            rom_hash: rom_hash(rom),
+            seed,
+            quirks,
+            instructions_per_frame,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn is_for_rom(&self, rom: &[u8]) -> bool {
+        self.rom_hash == rom_hash(rom)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn record(&mut self, key_map: &[bool; 16]) {
+        let mut keys = 0;
+        for (key, pressed) in key_map.iter().enumerate() {
+            if *pressed {
+                keys |= 1 << key;
+            }
+        }
+        self.frames.push(keys);
+    }
+
+    pub fn key_map(&self, frame: usize) -> Option<[bool; 16]> {
+        let keys = *self.frames.get(frame)?;
+        let mut key_map = [false; 16];
+        for (key, pressed) in key_map.iter_mut().enumerate() {
+            *pressed = keys & 1 << key != 0;
+        }
+        Some(key_map)
+    }
+
+    // A Cpu set up the way the recording started, with the rom loaded
+    pub fn start(&self, rom: &[u8]) -> Cpu {
+        let mut cpu = Cpu::new(self.quirks);
+        cpu.set_random(Random::new(self.seed));
+        cpu.load_rom(rom);
+        cpu
+    }
+
+    // Runs the whole movie without a window, for regression tests and bug reports
+    pub fn play(&self, rom: &[u8]) -> Result<Cpu, CpuError> {
+        let mut cpu = self.start(rom);
+        for frame in 0..self.frames.len() {
+            cpu.load_key_map(&self.key_map(frame).unwrap_or([false; 16]));
+            let instructions_per_frame = self.instructions_per_frame as usize;
+            if cpu.run_frame_ignoring_watchpoints(instructions_per_frame)? == StepOutcome::Halted {
+                break;
+            }
+        }
+        Ok(cpu)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.frames.len() * 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(MOVIE_VERSION);
+        bytes.extend_from_slice(&self.rom_hash);
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.push(self.quirks.to_bits());
+        bytes.extend_from_slice(&self.instructions_per_frame.to_be_bytes());
+        for keys in &self.frames {
+            bytes.extend_from_slice(&keys.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Movie, MovieError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(MovieError::NotAMovie);
+        }
+        if bytes.len() < HEADER_LEN || !(bytes.len() - HEADER_LEN).is_multiple_of(2) {
+            return Err(MovieError::Corrupted);
+        }
+        if bytes[4] != MOVIE_VERSION {
+            return Err(MovieError::UnsupportedVersion(bytes[4]));
+        }
+        let mut rom_hash = [0; 20];
+        rom_hash.copy_from_slice(&bytes[5..25]);
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&bytes[25..33]);
+        Ok(Movie {
+            rom_hash,
+            seed: u64::from_be_bytes(seed),
+            quirks: Quirks::from_bits(bytes[33]),
+            instructions_per_frame: u16::from_be_bytes([bytes[34], bytes[35]]),
+            frames: bytes[HEADER_LEN..]
+                .chunks(2)
+                .map(|keys| u16::from_be_bytes([keys[0], keys[1]]))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./movie_test.rs"]
+mod test;
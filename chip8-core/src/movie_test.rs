@@ -0,0 +1,63 @@
+use super::*;
+use crate::watchpoint::Access;
+
+// Waits for a key, adds a random number to V1 and draws it, forever
+const ROM: [u8; 12] = [
+    0xF0, 0x0A, 0xC2, 0xFF, 0x81, 0x24, 0xF1, 0x29, 0xD3, 0x45, 0x12, 0x00,
+];
+
+fn recorded_session() -> (Movie, Vec<u8>) {
+    record(|_| {})
+}
+
+fn record(set_up: fn(&mut Cpu)) -> (Movie, Vec<u8>) {
+    let mut movie = Movie::new(&ROM, 1234, Quirks::cosmac_vip(), 9);
+    let mut cpu = movie.start(&ROM);
+    set_up(&mut cpu);
+    for frame in 0..120 {
+        let mut key_map = [false; 16];
+        key_map[frame % 16] = frame % 3 == 0;
+        cpu.load_key_map(&key_map);
+        movie.record(&key_map);
+        cpu.run_frame_ignoring_watchpoints(9).unwrap();
+    }
+    (movie, cpu.save_state(&ROM))
+}
+
+#[test]
+fn replay_matches_recording_test() {
+    let (movie, expected) = recorded_session();
+    let cpu = movie.play(&ROM).unwrap();
+    assert_eq!(cpu.save_state(&ROM), expected);
+}
+
+// The replay has no watchpoints, the recording must not have been cut short by them
+#[test]
+fn replay_matches_recording_with_watchpoints_test() {
+    let (movie, expected) = record(|cpu| {
+        cpu.add_watchpoint(0x200..0x202, Access::Execute);
+        cpu.add_watchpoint(0..0x50, Access::Read);
+    });
+    let cpu = movie.play(&ROM).unwrap();
+    assert_eq!(cpu.save_state(&ROM), expected);
+}
+
+#[test]
+fn movie_bytes_round_trip_test() {
+    let (movie, _) = recorded_session();
+    let bytes = movie.to_bytes();
+    assert_eq!(bytes.len(), HEADER_LEN + 2 * 120);
+
+    let loaded = Movie::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, movie);
+    assert!(loaded.is_for_rom(&ROM));
+    assert!(!loaded.is_for_rom(&[0x00, 0xE0]));
+    assert!(loaded.key_map(3).unwrap()[3]);
+    assert_eq!(loaded.key_map(120), None);
+
+    assert_eq!(Movie::from_bytes(b"C8ST"), Err(MovieError::NotAMovie));
+    assert_eq!(
+        Movie::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(MovieError::Corrupted)
+    );
+}
@@ -0,0 +1,880 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::instruction::Instruction;
+use crate::symbols::SymbolMap;
+use crate::{PROGRAM_START, RAM};
+
+// Compiler for the Octo assembly language (https://github.com/JohnEarnest/Octo).
+// Supported: labels (`: name`), `:const`, `:alias`, `:macro`, `:calc`, `:byte`, `:org`,
+// `:unpack`, every Octo statement including `if ... then`, `if ... begin ... else ... end`
+// and `loop ... while ... again`, and bare numbers as data bytes.
+// A `jump main` is always placed at 0x200, like Octo does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctoError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for OctoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for OctoError {}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+struct Macro {
+    arguments: Vec<String>,
+    body: Vec<Token>,
+}
+
+#[derive(Clone, Copy)]
+enum Fixup {
+    // low 12 bits of the instruction at the address
+    Address,
+    // the whole word at the address, second half of `i := long`
+    Long,
+    // `v0 := nibble << 4 | label >> 8` from :unpack
+    UnpackHigh(u8),
+    // `v1 := label & 0xFF` from :unpack
+    UnpackLow,
+}
+
+enum Flow {
+    Begin { jump: usize },
+    Else { jump: usize },
+    Loop { start: usize, breaks: Vec<usize> },
+}
+
+enum Condition {
+    EqualValue(u8, u8),
+    NotEqualValue(u8, u8),
+    EqualRegister(u8, u8),
+    NotEqualRegister(u8, u8),
+    Pressed(u8),
+    NotPressed(u8),
+    // vx OP value, value is a register or a byte
+    Less(u8, Operand),
+    LessOrEqual(u8, Operand),
+    Greater(u8, Operand),
+    GreaterOrEqual(u8, Operand),
+}
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(u8),
+    Byte(u8),
+}
+
+impl Condition {
+    fn negate(self) -> Condition {
+        match self {
+            Condition::EqualValue(x, kk) => Condition::NotEqualValue(x, kk),
+            Condition::NotEqualValue(x, kk) => Condition::EqualValue(x, kk),
+            Condition::EqualRegister(x, y) => Condition::NotEqualRegister(x, y),
+            Condition::NotEqualRegister(x, y) => Condition::EqualRegister(x, y),
+            Condition::Pressed(x) => Condition::NotPressed(x),
+            Condition::NotPressed(x) => Condition::Pressed(x),
+            Condition::Less(x, value) => Condition::GreaterOrEqual(x, value),
+            Condition::LessOrEqual(x, value) => Condition::Greater(x, value),
+            Condition::Greater(x, value) => Condition::LessOrEqual(x, value),
+            Condition::GreaterOrEqual(x, value) => Condition::Less(x, value),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> VecDeque<Token> {
+    let mut tokens = VecDeque::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or("");
+        for text in code.split_whitespace() {
+            tokens.push_back(Token {
+                text: text.to_string(),
+                line: index + 1,
+            });
+        }
+    }
+    tokens
+}
+
+fn number(text: &str) -> Option<f64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()? as f64
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()? as f64
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix('v').or_else(|| text.strip_prefix('V'))?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+struct Compiler {
+    tokens: VecDeque<Token>,
+    rom: Vec<u8>,
+    here: usize,
+    line: usize,
+    labels: HashMap<String, usize>,
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<(usize, String, Fixup, usize)>,
+    flow: Vec<Flow>,
+}
+
+impl Compiler {
+    fn error<T>(&self, message: String) -> Result<T, OctoError> {
+        Err(OctoError {
+            line: self.line,
+            message,
+        })
+    }
+
+    fn next(&mut self) -> Result<String, OctoError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.line = token.line;
+                Ok(token.text)
+            }
+            None => self.error("unexpected end of file".to_string()),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if token != expected {
+            return self.error(format!("expected `{}`, found `{}`", expected, token));
+        }
+        Ok(())
+    }
+
+    fn write_byte(&mut self, address: usize, byte: u8) -> Result<(), OctoError> {
+        if !(PROGRAM_START..RAM).contains(&address) {
+            return self.error(format!("address 0x{:X} is outside program memory", address));
+        }
+        let index = address - PROGRAM_START;
+        if index >= self.rom.len() {
+            self.rom.resize(index + 1, 0);
+        }
+        self.rom[index] = byte;
+        Ok(())
+    }
+
+    fn emit_byte(&mut self, byte: u8) -> Result<(), OctoError> {
+        self.write_byte(self.here, byte)?;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn emit_word(&mut self, word: u16) -> Result<(), OctoError> {
+        let [high, low] = word.to_be_bytes();
+        self.emit_byte(high)?;
+        self.emit_byte(low)
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> Result<(), OctoError> {
+        self.emit_word(instruction.encode())
+    }
+
+    fn read_word(&self, address: usize) -> u16 {
+        let index = address - PROGRAM_START;
+        (self.rom[index] as u16) << 8 | self.rom[index + 1] as u16
+    }
+
+    fn patch_address(&mut self, address: usize, target: usize) -> Result<(), OctoError> {
+        if target > 0xFFF {
+            return self.error(format!("address 0x{:X} does not fit in 12 bits", target));
+        }
+        let word = self.read_word(address) & 0xF000 | target as u16;
+        self.write_byte(address, (word >> 8) as u8)?;
+        self.write_byte(address + 1, word as u8)
+    }
+
+    fn register(&mut self) -> Result<u8, OctoError> {
+        let token = self.next()?;
+        match register(&token).or_else(|| self.aliases.get(&token).copied()) {
+            Some(register) => Ok(register),
+            None => self.error(format!("expected a register, found `{}`", token)),
+        }
+    }
+
+    fn is_register(&self, token: &str) -> bool {
+        register(token).is_some() || self.aliases.contains_key(token)
+    }
+
+    // A number, constant or already defined label
+    fn known_value(&self, token: &str) -> Option<f64> {
+        number(token)
+            .or_else(|| self.constants.get(token).copied())
+            .or_else(|| self.labels.get(token).map(|address| *address as f64))
+    }
+
+    fn value(&mut self) -> Result<f64, OctoError> {
+        let token = self.next()?;
+        match self.known_value(&token) {
+            Some(value) => Ok(value),
+            None => self.error(format!("unknown value `{}`", token)),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, OctoError> {
+        let value = self.value()? as i64;
+        if !(-128..=255).contains(&value) {
+            return self.error(format!("byte {} out of range", value));
+        }
+        Ok(value as u8)
+    }
+
+    fn nibble(&mut self) -> Result<u8, OctoError> {
+        let value = self.value()? as i64;
+        if !(0..=15).contains(&value) {
+            return self.error(format!("nibble {} out of range", value));
+        }
+        Ok(value as u8)
+    }
+
+    // Emits an instruction whose address may be a label defined further down
+    fn emit_with_address(
+        &mut self,
+        instruction: Instruction,
+        fixup: Fixup,
+    ) -> Result<(), OctoError> {
+        let token = self.next()?;
+        let address = self.here;
+        self.emit(instruction)?;
+        match fixup {
+            Fixup::Long => self.emit_word(0)?,
+            Fixup::UnpackHigh(_) | Fixup::UnpackLow => unreachable!(),
+            Fixup::Address => {}
+        }
+        match self.known_value(&token) {
+            Some(target) => self.apply_fixup(address, target as usize, fixup),
+            None => {
+                self.fixups.push((address, token, fixup, self.line));
+                Ok(())
+            }
+        }
+    }
+
+    fn apply_fixup(
+        &mut self,
+        address: usize,
+        target: usize,
+        fixup: Fixup,
+    ) -> Result<(), OctoError> {
+        match fixup {
+            Fixup::Address => self.patch_address(address, target),
+            Fixup::Long => {
+                if target > 0xFFFF {
+                    return self.error(format!("address 0x{:X} does not fit in 16 bits", target));
+                }
+                self.write_byte(address + 2, (target >> 8) as u8)?;
+                self.write_byte(address + 3, target as u8)
+            }
+            Fixup::UnpackHigh(nibble) => {
+                self.write_byte(address + 1, nibble << 4 | (target >> 8) as u8 & 0xF)
+            }
+            Fixup::UnpackLow => self.write_byte(address + 1, target as u8),
+        }
+    }
+
+    // Emits code after which the next instruction is skipped when the condition holds
+    fn emit_skip_if(&mut self, condition: Condition) -> Result<(), OctoError> {
+        match condition {
+            Condition::EqualValue(x, kk) => self.emit(Instruction::SkipIfEqual(x, kk)),
+            Condition::NotEqualValue(x, kk) => self.emit(Instruction::SkipIfNotEqual(x, kk)),
+            Condition::EqualRegister(x, y) => self.emit(Instruction::SkipIfRegistersEqual(x, y)),
+            Condition::NotEqualRegister(x, y) => {
+                self.emit(Instruction::SkipIfRegistersNotEqual(x, y))
+            }
+            Condition::Pressed(x) => self.emit(Instruction::SkipIfPressed(x)),
+            Condition::NotPressed(x) => self.emit(Instruction::SkipIfNotPressed(x)),
+            // vf := value, then vf =- vx leaves VF = vx >= value
+            // while vf -= vx leaves VF = value >= vx
+            Condition::Less(x, value)
+            | Condition::LessOrEqual(x, value)
+            | Condition::Greater(x, value)
+            | Condition::GreaterOrEqual(x, value) => {
+                match value {
+                    Operand::Register(y) => self.emit(Instruction::Copy(0xF, y))?,
+                    Operand::Byte(kk) => self.emit(Instruction::Store(0xF, kk))?,
+                }
+                let (subtraction, flag) = match condition {
+                    Condition::Less(..) => (Instruction::SubReverse(0xF, x), 0),
+                    Condition::GreaterOrEqual(..) => (Instruction::SubReverse(0xF, x), 1),
+                    Condition::LessOrEqual(..) => (Instruction::Sub(0xF, x), 1),
+                    _ => (Instruction::Sub(0xF, x), 0),
+                };
+                self.emit(subtraction)?;
+                self.emit(Instruction::SkipIfEqual(0xF, flag))
+            }
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, OctoError> {
+        let x = self.register()?;
+        let operator = self.next()?;
+        match operator.as_str() {
+            "key" => return Ok(Condition::Pressed(x)),
+            "-key" => return Ok(Condition::NotPressed(x)),
+            _ => {}
+        }
+        let operand = match self.peek() {
+            Some(token) if self.is_register(token) => Operand::Register(self.register()?),
+            _ => Operand::Byte(self.byte()?),
+        };
+        let condition = match (operator.as_str(), operand) {
+            ("==", Operand::Byte(kk)) => Condition::EqualValue(x, kk),
+            ("!=", Operand::Byte(kk)) => Condition::NotEqualValue(x, kk),
+            ("==", Operand::Register(y)) => Condition::EqualRegister(x, y),
+            ("!=", Operand::Register(y)) => Condition::NotEqualRegister(x, y),
+            ("<", operand) => Condition::Less(x, operand),
+            ("<=", operand) => Condition::LessOrEqual(x, operand),
+            (">", operand) => Condition::Greater(x, operand),
+            (">=", operand) => Condition::GreaterOrEqual(x, operand),
+            _ => return self.error(format!("unknown comparison `{}`", operator)),
+        };
+        Ok(condition)
+    }
+
+    fn placeholder_jump(&mut self) -> Result<usize, OctoError> {
+        let address = self.here;
+        self.emit(Instruction::Jump(0))?;
+        Ok(address)
+    }
+
+    // Tokens between `{` and the matching `}`
+    fn braces(&mut self) -> Result<Vec<Token>, OctoError> {
+        self.expect("{")?;
+        let mut depth = 1;
+        let mut body = Vec::new();
+        loop {
+            let token = self.next()?;
+            match token.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(body);
+                    }
+                }
+                _ => {}
+            }
+            body.push(Token {
+                text: token,
+                line: self.line,
+            });
+        }
+    }
+
+    // :calc expressions have no precedence and are evaluated right to left,
+    // just like in Octo: `2 * 3 + 1` is 8
+    fn calc(&self, tokens: &[Token]) -> Result<f64, OctoError> {
+        let (value, rest) = self.calc_expression(tokens)?;
+        if let Some(token) = rest.first() {
+            return self.error(format!("unexpected `{}` in expression", token.text));
+        }
+        Ok(value)
+    }
+
+    fn calc_expression<'t>(&self, tokens: &'t [Token]) -> Result<(f64, &'t [Token]), OctoError> {
+        let (left, rest) = self.calc_term(tokens)?;
+        let operator = match rest.first() {
+            Some(token) if token.text != ")" => token.text.as_str(),
+            _ => return Ok((left, rest)),
+        };
+        let (right, rest) = self.calc_expression(&rest[1..])?;
+        let value = match operator {
+            "+" => left + right,
+            "-" => left - right,
+            "*" => left * right,
+            "/" => left / right,
+            "%" => left % right,
+            "&" => (left as i64 & right as i64) as f64,
+            "|" => (left as i64 | right as i64) as f64,
+            "^" => (left as i64 ^ right as i64) as f64,
+            "<<" => ((left as i64) << right as i64) as f64,
+            ">>" => (left as i64 >> right as i64) as f64,
+            "pow" => left.powf(right),
+            "min" => left.min(right),
+            "max" => left.max(right),
+            "<" => (left < right) as i64 as f64,
+            "<=" => (left <= right) as i64 as f64,
+            ">" => (left > right) as i64 as f64,
+            ">=" => (left >= right) as i64 as f64,
+            "==" => (left == right) as i64 as f64,
+            "!=" => (left != right) as i64 as f64,
+            _ => return self.error(format!("unknown operator `{}`", operator)),
+        };
+        Ok((value, rest))
+    }
+
+    fn calc_term<'t>(&self, tokens: &'t [Token]) -> Result<(f64, &'t [Token]), OctoError> {
+        let token = match tokens.first() {
+            Some(token) => token.text.as_str(),
+            None => return self.error("incomplete expression".to_string()),
+        };
+        let rest = &tokens[1..];
+        if token == "(" {
+            let (value, rest) = self.calc_expression(rest)?;
+            return match rest.first() {
+                Some(close) if close.text == ")" => Ok((value, &rest[1..])),
+                _ => self.error("missing `)` in expression".to_string()),
+            };
+        }
+        let unary: Option<fn(f64) -> f64> = match token {
+            "-" => Some(|value| -value),
+            "~" => Some(|value| !(value as i64) as f64),
+            "!" => Some(|value| (value == 0.0) as i64 as f64),
+            "abs" => Some(f64::abs),
+            "sqrt" => Some(f64::sqrt),
+            "sin" => Some(f64::sin),
+            "cos" => Some(f64::cos),
+            "tan" => Some(f64::tan),
+            "exp" => Some(f64::exp),
+            "log" => Some(f64::ln),
+            "floor" => Some(f64::floor),
+            "ceil" => Some(f64::ceil),
+            "sign" => Some(f64::signum),
+            _ => None,
+        };
+        if let Some(unary) = unary {
+            let (value, rest) = self.calc_term(rest)?;
+            return Ok((unary(value), rest));
+        }
+        let value = match token {
+            "PI" => std::f64::consts::PI,
+            "E" => std::f64::consts::E,
+            "HERE" => self.here as f64,
+            _ => match self.known_value(token) {
+                Some(value) => value,
+                None => return self.error(format!("unknown value `{}` in expression", token)),
+            },
+        };
+        Ok((value, rest))
+    }
+
+    fn directive(&mut self, directive: &str) -> Result<(), OctoError> {
+        match directive {
+            ":" => {
+                let name = self.next()?;
+                if self.labels.insert(name.clone(), self.here).is_some() {
+                    return self.error(format!("label `{}` is defined twice", name));
+                }
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.value()?;
+                self.constants.insert(name, value);
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+            }
+            ":calc" => {
+                let name = self.next()?;
+                let body = self.braces()?;
+                let value = self.calc(&body)?;
+                self.constants.insert(name, value);
+            }
+            ":byte" => {
+                let value = if self.peek() == Some("{") {
+                    let body = self.braces()?;
+                    self.calc(&body)?
+                } else {
+                    self.value()?
+                };
+                self.emit_byte(value as i64 as u8)?;
+            }
+            ":org" => self.here = self.value()? as usize,
+            ":macro" => {
+                let name = self.next()?;
+                let mut arguments = Vec::new();
+                while self.peek().is_some_and(|token| token != "{") {
+                    arguments.push(self.next()?);
+                }
+                let body = self.braces()?;
+                self.macros.insert(name, Macro { arguments, body });
+            }
+            ":unpack" => {
+                let nibble = match self.peek() {
+                    Some("long") => {
+                        self.next()?;
+                        0
+                    }
+                    _ => self.nibble()?,
+                };
+                let label = self.next()?;
+                let address = self.here;
+                self.emit(Instruction::Store(0, 0))?;
+                self.emit(Instruction::Store(1, 0))?;
+                let target = self.labels.get(&label).copied();
+                let fixups = [
+                    (address, Fixup::UnpackHigh(nibble)),
+                    (address + 2, Fixup::UnpackLow),
+                ];
+                for (address, fixup) in fixups {
+                    match target {
+                        Some(target) => self.apply_fixup(address, target, fixup)?,
+                        None => self.fixups.push((address, label.clone(), fixup, self.line)),
+                    }
+                }
+            }
+            ":breakpoint" => {
+                self.next()?;
+            }
+            ":monitor" => {
+                self.next()?;
+                self.next()?;
+            }
+            _ => return self.error(format!("unsupported directive `{}`", directive)),
+        }
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), OctoError> {
+        let (arguments, body) = match self.macros.get(name) {
+            Some(definition) => (definition.arguments.clone(), definition.body.clone()),
+            None => return Ok(()),
+        };
+        let mut values = HashMap::new();
+        for argument in arguments {
+            values.insert(argument, self.next()?);
+        }
+        for token in body.into_iter().rev() {
+            let text = values.get(&token.text).cloned().unwrap_or(token.text);
+            self.tokens.push_front(Token {
+                text,
+                line: token.line,
+            });
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if self.macros.contains_key(&token) {
+            return self.expand_macro(&token);
+        }
+        if token.starts_with(':') {
+            return self.directive(&token);
+        }
+        if self.is_register(&token) {
+            self.tokens.push_front(Token {
+                text: token,
+                line: self.line,
+            });
+            return self.register_statement();
+        }
+        match token.as_str() {
+            "clear" => self.emit(Instruction::ClearScreen)?,
+            "return" | ";" => self.emit(Instruction::Return)?,
+            "exit" => self.emit(Instruction::Exit)?,
+            "hires" => self.emit(Instruction::HighRes)?,
+            "lores" => self.emit(Instruction::LowRes)?,
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollDown(n))?
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollUp(n))?
+            }
+            "scroll-left" => self.emit(Instruction::ScrollLeft)?,
+            "scroll-right" => self.emit(Instruction::ScrollRight)?,
+            "audio" => self.emit(Instruction::LoadAudioPattern)?,
+            "plane" => {
+                let n = self.nibble()?;
+                if n > 3 {
+                    return self.error(format!("plane {} out of range", n));
+                }
+                self.emit(Instruction::SelectPlane(n))?
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(Instruction::StoreBcd(x))?
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                if self.peek() == Some("-") {
+                    self.next()?;
+                    let y = self.register()?;
+                    if token == "save" {
+                        self.emit(Instruction::SaveRange(x, y))?
+                    } else {
+                        self.emit(Instruction::LoadRange(x, y))?
+                    }
+                } else if token == "save" {
+                    self.emit(Instruction::SaveRegisters(x))?
+                } else {
+                    self.emit(Instruction::LoadRegisters(x))?
+                }
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::SaveFlags(x))?
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::LoadFlags(x))?
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(Instruction::Draw(x, y, n))?
+            }
+            "jump" => self.emit_with_address(Instruction::Jump(0), Fixup::Address)?,
+            "jump0" => self.emit_with_address(Instruction::JumpWithOffset(0), Fixup::Address)?,
+            "native" => {
+                let token = self.next()?;
+                let address = self.here;
+                self.emit_word(0)?;
+                match self.known_value(&token) {
+                    Some(target) => self.patch_address(address, target as usize)?,
+                    None => self
+                        .fixups
+                        .push((address, token, Fixup::Address, self.line)),
+                }
+            }
+            "i" => self.i_statement()?,
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                match token.as_str() {
+                    "delay" => self.emit(Instruction::SetDelay(x))?,
+                    "buzzer" => self.emit(Instruction::SetSound(x))?,
+                    _ => self.emit(Instruction::SetPitch(x))?,
+                }
+            }
+            "if" => {
+                let condition = self.condition()?;
+                match self.next()?.as_str() {
+                    "then" => self.emit_skip_if(condition.negate())?,
+                    "begin" => {
+                        self.emit_skip_if(condition)?;
+                        let jump = self.placeholder_jump()?;
+                        self.flow.push(Flow::Begin { jump });
+                    }
+                    other => {
+                        return self.error(format!("expected then or begin, found `{}`", other))
+                    }
+                }
+            }
+            "else" => match self.flow.pop() {
+                Some(Flow::Begin { jump }) => {
+                    let end = self.placeholder_jump()?;
+                    self.patch_address(jump, self.here)?;
+                    self.flow.push(Flow::Else { jump: end });
+                }
+                _ => return self.error("else without if ... begin".to_string()),
+            },
+            "end" => match self.flow.pop() {
+                Some(Flow::Begin { jump }) | Some(Flow::Else { jump }) => {
+                    self.patch_address(jump, self.here)?
+                }
+                _ => return self.error("end without if ... begin".to_string()),
+            },
+            "loop" => self.flow.push(Flow::Loop {
+                start: self.here,
+                breaks: Vec::new(),
+            }),
+            "while" => {
+                let condition = self.condition()?;
+                self.emit_skip_if(condition)?;
+                let jump = self.placeholder_jump()?;
+                match self
+                    .flow
+                    .iter_mut()
+                    .rev()
+                    .find(|flow| matches!(flow, Flow::Loop { .. }))
+                {
+                    Some(Flow::Loop { breaks, .. }) => breaks.push(jump),
+                    _ => return self.error("while outside of loop".to_string()),
+                }
+            }
+            "again" => match self.flow.pop() {
+                Some(Flow::Loop { start, breaks }) => {
+                    self.emit(Instruction::Jump(0))?;
+                    self.patch_address(self.here - 2, start)?;
+                    for jump in breaks {
+                        self.patch_address(jump, self.here)?;
+                    }
+                }
+                _ => return self.error("again without loop".to_string()),
+            },
+            _ => match number(&token).or_else(|| self.constants.get(&token).copied()) {
+                Some(value) => {
+                    let value = value as i64;
+                    if !(-128..=255).contains(&value) {
+                        return self.error(format!("byte {} out of range", value));
+                    }
+                    self.emit_byte(value as u8)?
+                }
+                // anything else is a call to a label, possibly defined later
+                None => {
+                    self.tokens.push_front(Token {
+                        text: token,
+                        line: self.line,
+                    });
+                    self.emit_with_address(Instruction::Call(0), Fixup::Address)?
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn i_statement(&mut self) -> Result<(), OctoError> {
+        let operator = self.next()?;
+        match operator.as_str() {
+            "+=" => {
+                let x = self.register()?;
+                self.emit(Instruction::AddToI(x))
+            }
+            ":=" => match self.peek() {
+                Some("hex") => {
+                    self.next()?;
+                    let x = self.register()?;
+                    self.emit(Instruction::LoadFont(x))
+                }
+                Some("bighex") => {
+                    self.next()?;
+                    let x = self.register()?;
+                    self.emit(Instruction::LoadBigFont(x))
+                }
+                Some("long") => {
+                    self.next()?;
+                    self.emit_with_address(Instruction::LoadILong, Fixup::Long)
+                }
+                _ => self.emit_with_address(Instruction::LoadI(0), Fixup::Address),
+            },
+            _ => self.error(format!("unknown operator `i {}`", operator)),
+        }
+    }
+
+    fn register_statement(&mut self) -> Result<(), OctoError> {
+        let x = self.register()?;
+        let operator = self.next()?;
+        if operator == ":=" {
+            match self.peek() {
+                Some("random") => {
+                    self.next()?;
+                    let kk = self.byte()?;
+                    return self.emit(Instruction::Random(x, kk));
+                }
+                Some("delay") => {
+                    self.next()?;
+                    return self.emit(Instruction::StoreDelay(x));
+                }
+                Some("key") => {
+                    self.next()?;
+                    return self.emit(Instruction::WaitForKey(x));
+                }
+                _ => {}
+            }
+        }
+        let operand = match self.peek() {
+            Some(token) if self.is_register(token) => Operand::Register(self.register()?),
+            _ => Operand::Byte(self.byte()?),
+        };
+        let instruction = match (operator.as_str(), operand) {
+            (":=", Operand::Byte(kk)) => Instruction::Store(x, kk),
+            (":=", Operand::Register(y)) => Instruction::Copy(x, y),
+            ("+=", Operand::Byte(kk)) => Instruction::Add(x, kk),
+            ("+=", Operand::Register(y)) => Instruction::AddRegister(x, y),
+            ("-=", Operand::Byte(kk)) => Instruction::Add(x, kk.wrapping_neg()),
+            ("-=", Operand::Register(y)) => Instruction::Sub(x, y),
+            ("=-", Operand::Register(y)) => Instruction::SubReverse(x, y),
+            ("|=", Operand::Register(y)) => Instruction::Or(x, y),
+            ("&=", Operand::Register(y)) => Instruction::And(x, y),
+            ("^=", Operand::Register(y)) => Instruction::Xor(x, y),
+            (">>=", Operand::Register(y)) => Instruction::ShiftRight(x, y),
+            ("<<=", Operand::Register(y)) => Instruction::ShiftLeft(x, y),
+            _ => return self.error(format!("bad operands for `{}`", operator)),
+        };
+        self.emit(instruction)
+    }
+}
+
+// Compiles Octo source into a ROM image that starts at PROGRAM_START
+pub fn compile(source: &str) -> Result<Vec<u8>, OctoError> {
+    compile_with_symbols(source).map(|(rom, _)| rom)
+}
+
+// Same as compile, also returns the address of every label and of the code of every line
+pub fn compile_with_symbols(source: &str) -> Result<(Vec<u8>, SymbolMap), OctoError> {
+    let mut symbols = SymbolMap::new();
+    let mut compiler = Compiler {
+        tokens: tokenize(source),
+        rom: Vec::new(),
+        here: PROGRAM_START,
+        line: 1,
+        labels: HashMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        macros: HashMap::new(),
+        fixups: Vec::new(),
+        flow: Vec::new(),
+    };
+    compiler.emit(Instruction::Jump(0))?;
+    compiler
+        .fixups
+        .push((PROGRAM_START, "main".to_string(), Fixup::Address, 1));
+
+    while let Some(token) = compiler.tokens.front() {
+        // directives and data bytes are not code a debugger could stop on
+        let code = !token.text.starts_with(':') && number(&token.text).is_none();
+        let (line, start) = (token.line, compiler.here);
+        compiler.statement()?;
+        if code && compiler.here > start {
+            symbols.add_line(line, start);
+        }
+    }
+    if let Some(flow) = compiler.flow.last() {
+        let unclosed = match flow {
+            Flow::Loop { .. } => "loop without again",
+            _ => "if ... begin without end",
+        };
+        return compiler.error(unclosed.to_string());
+    }
+    for (address, label, fixup, line) in std::mem::take(&mut compiler.fixups) {
+        compiler.line = line;
+        match compiler.labels.get(&label).copied() {
+            Some(target) => compiler.apply_fixup(address, target, fixup)?,
+            None => return compiler.error(format!("undefined label `{}`", label)),
+        }
+    }
+    for (name, address) in &compiler.labels {
+        symbols.add_label(name, *address);
+    }
+    Ok((compiler.rom, symbols))
+}
+
+#[cfg(test)]
+#[path = "./octo_test.rs"]
+mod test;
@@ -0,0 +1,150 @@
+use super::*;
+use crate::processor::Cpu;
+use crate::quirks::Quirks;
+
+// Runs the compiled source and tells whether the top left pixel got drawn
+fn draws(source: &str) -> bool {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&compile(source).unwrap());
+    for _ in 0..20 {
+        cpu.run_next_instruction().unwrap();
+    }
+    cpu.read_pixels()[0][0] != 0
+}
+
+#[test]
+fn compile_main_jump_test() {
+    let rom = compile(": main loop again").unwrap();
+    assert_eq!(rom, vec![0x12, 0x02, 0x12, 0x02]);
+
+    let rom = compile("0xAA : main exit").unwrap();
+    assert_eq!(rom, vec![0x12, 0x03, 0xAA, 0x00, 0xFD]);
+}
+
+#[test]
+fn compile_statements_test() {
+    let source = "
+        : main
+            clear
+            v1 := 0x20  # comment
+            v1 += v2
+            v3 -= 1
+            i := sprite
+            sprite v0 v1 2
+            save v3
+            load v1 - v4
+            delay := v1
+            va := key
+            i := long sprite
+            return
+        : sprite 0xFF 0x81
+    ";
+    let rom = compile(source).unwrap();
+    assert_eq!(
+        rom,
+        vec![
+            0x12, 0x02, 0x00, 0xE0, 0x61, 0x20, 0x81, 0x24, 0x73, 0xFF, 0xA2, 0x1C, 0xD0, 0x12,
+            0xF3, 0x55, 0x51, 0x43, 0xF1, 0x15, 0xFA, 0x0A, 0xF0, 0x00, 0x02, 0x1C, 0x00, 0xEE,
+            0xFF, 0x81,
+        ]
+    );
+}
+
+#[test]
+fn compile_conditionals_test() {
+    let rom = compile(": main if v0 == 3 then v1 := 1").unwrap();
+    assert_eq!(rom, vec![0x12, 0x02, 0x40, 0x03, 0x61, 0x01]);
+
+    let rom = compile(": main if v0 key begin v1 := 1 else v1 := 2 end exit").unwrap();
+    assert_eq!(
+        rom,
+        vec![0x12, 0x02, 0xE0, 0x9E, 0x12, 0x0A, 0x61, 0x01, 0x12, 0x0C, 0x61, 0x02, 0x00, 0xFD]
+    );
+
+    // vf := 10, vf =- v2 leaves VF = v2 >= 10
+    let rom = compile(": main if v2 < 10 then clear").unwrap();
+    assert_eq!(
+        rom,
+        vec![0x12, 0x02, 0x6F, 0x0A, 0x8F, 0x27, 0x3F, 0x01, 0x00, 0xE0]
+    );
+}
+
+#[test]
+fn compile_comparison_equal_test() {
+    let program = |first, comparison| {
+        format!(
+            ": main v0 := {} v1 := 5 v2 := 0 i := dot if v0 {} v1 then sprite v2 v2 1 loop again : dot 0x80",
+            first, comparison
+        )
+    };
+    assert!(draws(&program(5, "<=")));
+    assert!(draws(&program(5, ">=")));
+    assert!(!draws(&program(5, "<")));
+    assert!(!draws(&program(5, ">")));
+    assert!(!draws(&program(6, "<=")));
+    assert!(!draws(&program(4, ">=")));
+}
+
+#[test]
+fn compile_loop_test() {
+    let rom = compile(": main loop v0 += 1 while v0 != 5 again exit").unwrap();
+    assert_eq!(
+        rom,
+        vec![0x12, 0x02, 0x70, 0x01, 0x40, 0x05, 0x12, 0x0A, 0x12, 0x02, 0x00, 0xFD]
+    );
+}
+
+#[test]
+fn compile_forward_call_test() {
+    let rom = compile(": main draw exit : draw clear ;").unwrap();
+    assert_eq!(
+        rom,
+        vec![0x12, 0x02, 0x22, 0x06, 0x00, 0xFD, 0x00, 0xE0, 0x00, 0xEE]
+    );
+}
+
+#[test]
+fn compile_directives_test() {
+    let source = "
+        :const SPEED 3
+        :alias x v4
+        :calc DOUBLE { SPEED * 2 + 1 }
+        :macro move register amount { register += amount }
+        : main
+            move x SPEED
+            x := DOUBLE
+            :unpack 0xA main
+            :byte { 1 - 2 - 3 }
+    ";
+    let rom = compile(source).unwrap();
+    assert_eq!(
+        rom,
+        vec![0x12, 0x02, 0x74, 0x03, 0x64, 0x09, 0x60, 0xA2, 0x61, 0x02, 0x02]
+    );
+}
+
+#[test]
+fn compile_error_test() {
+    let error = compile(": main\n  jump nowhere").unwrap_err();
+    assert_eq!(error.to_string(), "line 2: undefined label `nowhere`");
+
+    let error = compile("clear").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: undefined label `main`");
+
+    let error = compile(": main\nloop\nclear").unwrap_err();
+    assert_eq!(error.to_string(), "line 3: loop without again");
+
+    let error = compile(": main v0 := 300").unwrap_err();
+    assert_eq!(error.to_string(), "line 1: byte 300 out of range");
+}
+
+#[test]
+fn compile_with_symbols_test() {
+    let source = ": main\n  v0 := 1\n\n  loop v0 += 1 again\n: data\n  1 2";
+    let (rom, symbols) = compile_with_symbols(source).unwrap();
+    assert_eq!(rom.len(), 10);
+    assert_eq!(symbols.line_address(1), Some((2, 0x202)));
+    assert_eq!(symbols.line_address(3), Some((4, 0x204)));
+    assert_eq!(symbols.line_address(5), None);
+    assert_eq!(symbols.label_address("data"), Some(0x208));
+}
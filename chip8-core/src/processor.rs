@@ -0,0 +1,860 @@
+use std::fmt;
+use std::ops::Range;
+
+use super::HEIGHT;
+use super::HIRES_HEIGHT;
+use super::HIRES_WIDTH;
+use super::PROGRAM_START;
+use super::RAM;
+use super::WIDTH;
+use crate::instruction::Instruction;
+use crate::quirks::Quirks;
+use crate::random::Random;
+use crate::trace::Tracer;
+use crate::watchpoint::{Access, WatchHit, Watchpoint};
+
+const CHIP8_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+const SCHIP_FONT: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+const SCHIP_FONT_START: usize = CHIP8_FONT.len();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Executed,
+    // FX0A is waiting for a key, the same instruction runs again next step
+    WaitingForKey,
+    // 00FD was executed, further steps do nothing
+    Halted,
+    // The instruction ran and touched a watched address
+    Watchpoint(WatchHit),
+}
+
+// Every error carries the address of the instruction that caused it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuError {
+    UnknownOpcode {
+        opcode: u16,
+        address: usize,
+    },
+    StackOverflow {
+        address: usize,
+    },
+    StackUnderflow {
+        address: usize,
+    },
+    // An instruction tried to access memory at I + offset past the end of RAM
+    MemoryOutOfRange {
+        i: u16,
+        offset: usize,
+        address: usize,
+    },
+    ProgramCounterOutOfRange {
+        address: usize,
+    },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::UnknownOpcode { opcode, address } => {
+                write!(f, "unknown opcode {:04X} at {:03X}", opcode, address)
+            }
+            CpuError::StackOverflow { address } => write!(f, "stack overflow at {:03X}", address),
+            CpuError::StackUnderflow { address } => {
+                write!(f, "stack underflow at {:03X}", address)
+            }
+            CpuError::MemoryOutOfRange { i, offset, address } => write!(
+                f,
+                "memory access out of range (I = {:04X} + {}) at {:03X}",
+                i, offset, address
+            ),
+            CpuError::ProgramCounterOutOfRange { address } => {
+                write!(
+                    f,
+                    "program counter ran off the end of memory at {:03X}",
+                    address
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+#[derive(Debug)]
+pub struct Cpu {
+    pub(crate) memory: [u8; RAM],
+    pub(crate) register: [u8; 16],
+    pub(crate) program_counter: usize,
+    pub(crate) stack: [u16; 16],
+    pub(crate) stack_pointer: u8,
+    pub(crate) i: u16,
+    pub(crate) delay_timer: u8,
+    pub(crate) sound_timer: u8,
+    pub(crate) pixels: [[u8; HIRES_WIDTH]; HIRES_HEIGHT],
+    pub(crate) hires: bool,
+    pub(crate) plane: u8,
+    pub(crate) pitch: u8,
+    pub(crate) audio_pattern: Option<[u8; 16]>,
+    pub(crate) rpl_flags: [u8; 16],
+    pub(crate) halted: bool,
+    pub keypad: [bool; 16],
+    pub(crate) quirks: Quirks,
+    pub(crate) random: Random,
+    pub(crate) watchpoints: Vec<Watchpoint>,
+    pub(crate) watch_hit: Option<WatchHit>,
+    // program counter of the instruction being executed, for watch hits
+    pub(crate) instruction_address: usize,
+    pub(crate) tracer: Option<Tracer>,
+}
+
+impl Cpu {
+    pub fn new(quirks: Quirks) -> Cpu {
+        let mut cpu = Cpu {
+            memory: [0; RAM],
+            register: [0; 16],
+            program_counter: PROGRAM_START,
+            stack: [0; 16],
+            stack_pointer: 0,
+            i: 0,
+            delay_timer: 0,
+            sound_timer: 0,
+            pixels: [[0; HIRES_WIDTH]; HIRES_HEIGHT],
+            hires: false,
+            plane: 1,
+            pitch: 64,
+            audio_pattern: None,
+            rpl_flags: [0; 16],
+            halted: false,
+            keypad: [false; 16],
+            quirks,
+            random: Random::default(),
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            instruction_address: PROGRAM_START,
+            tracer: None,
+        };
+        cpu.memory[..CHIP8_FONT.len()].clone_from_slice(&CHIP8_FONT);
+        cpu.memory[SCHIP_FONT_START..SCHIP_FONT_START + SCHIP_FONT.len()]
+            .clone_from_slice(&SCHIP_FONT);
+        cpu
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // CXNN draws from this, seed it to make a run reproducible
+    pub fn set_random(&mut self, random: Random) {
+        self.random = random;
+    }
+
+    pub fn random(&self) -> Random {
+        self.random
+    }
+
+    pub fn check_sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    // Whatever does not fit into memory past PROGRAM_START is left out
+    pub fn load_rom(&mut self, rom: &[u8]) {
+        let rom = &rom[..rom.len().min(RAM - PROGRAM_START)];
+        let end = PROGRAM_START + rom.len();
+        self.memory[PROGRAM_START..end].clone_from_slice(rom);
+    }
+
+    // Only the top left resolution() part of the returned buffer is in use
+    pub fn read_pixels(&self) -> &[[u8; HIRES_WIDTH]; HIRES_HEIGHT] {
+        &self.pixels
+    }
+
+    pub fn resolution(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (WIDTH, HEIGHT)
+        }
+    }
+
+    pub fn audio_pattern(&self) -> Option<&[u8; 16]> {
+        self.audio_pattern.as_ref()
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    // Read only views for debuggers and other tools
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.register
+    }
+
+    pub fn i(&self) -> u16 {
+        self.i
+    }
+
+    pub fn stack(&self) -> &[u16; 16] {
+        &self.stack
+    }
+
+    pub fn stack_pointer(&self) -> u8 {
+        self.stack_pointer
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn memory(&self) -> &[u8; RAM] {
+        &self.memory
+    }
+
+    // Bytes that do not fit into memory are dropped
+    pub fn write_memory(&mut self, address: usize, bytes: &[u8]) {
+        let start = address.min(RAM);
+        let end = (start + bytes.len()).min(RAM);
+        self.memory[start..end].copy_from_slice(&bytes[..end - start]);
+    }
+
+    fn what_key_is_pressed(&self) -> Option<u8> {
+        for (key, pressed) in self.keypad.iter().enumerate() {
+            if *pressed {
+                return Some(key as u8);
+            }
+        }
+        None
+    }
+
+    pub fn load_key_map(&mut self, key_map: &[bool; 16]) {
+        for (key, state) in key_map.iter().enumerate() {
+            self.keypad[key] = *state;
+        }
+    }
+
+    // Watchpoints belong to the game that is running and go with it
+    pub fn reset(&mut self) {
+        let random = self.random;
+        let tracer = self.tracer.take();
+        *self = Cpu::new(self.quirks);
+        self.random = random;
+        self.tracer = tracer;
+    }
+
+    // Jumps to 0x000 step the program counter below zero for a moment
+    fn program_counter_decrease(&mut self) {
+        self.program_counter = self.program_counter.wrapping_sub(2);
+    }
+
+    fn program_counter_increase(&mut self) {
+        self.program_counter = self.program_counter.wrapping_add(2);
+    }
+
+    // F000 NNNN is the only instruction four bytes long,
+    // skips have to jump over all of it
+    fn skip_next_instruction(&mut self) {
+        self.program_counter_increase();
+        if self.next_opcode() == Ok(0xF000) {
+            self.program_counter_increase();
+        }
+    }
+
+    fn next_opcode(&self) -> Result<u16, CpuError> {
+        if self.program_counter >= RAM - 1 {
+            return Err(CpuError::ProgramCounterOutOfRange {
+                address: self.program_counter,
+            });
+        }
+        let op1 = self.memory[self.program_counter];
+        let op2 = self.memory[self.program_counter + 1];
+
+        Ok(((op1 as u16) << 8) | op2 as u16)
+    }
+
+    // Range of `len` bytes of memory starting at I + offset, about to be accessed
+    fn memory_at_i(
+        &mut self,
+        offset: usize,
+        len: usize,
+        access: Access,
+    ) -> Result<Range<usize>, CpuError> {
+        let start = self.i as usize + offset;
+        if start + len > RAM {
+            return Err(CpuError::MemoryOutOfRange {
+                i: self.i,
+                offset: offset + len - 1,
+                address: self.program_counter,
+            });
+        }
+        self.watch(start..start + len, access);
+        Ok(start..start + len)
+    }
+
+    pub fn run_next_instruction(&mut self) -> Result<StepOutcome, CpuError> {
+        let outcome = self.step();
+        // taken on errors too, or the next instruction would report this one's hit
+        let watch_hit = self.watch_hit.take();
+        match (outcome?, watch_hit) {
+            (outcome, Some(hit)) if outcome != StepOutcome::Halted => {
+                Ok(StepOutcome::Watchpoint(hit))
+            }
+            (outcome, _) => Ok(outcome),
+        }
+    }
+
+    fn step(&mut self) -> Result<StepOutcome, CpuError> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+        let opcode = self.next_opcode()?;
+        self.instruction_address = self.program_counter;
+        self.watch(
+            self.program_counter..self.program_counter + 2,
+            Access::Execute,
+        );
+        let instruction = Instruction::decode(opcode).ok_or(CpuError::UnknownOpcode {
+            opcode,
+            address: self.program_counter,
+        })?;
+        let outcome = self.execute(instruction)?;
+
+        self.program_counter_increase();
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.trace(self, self.instruction_address, opcode);
+            self.tracer = Some(tracer);
+        }
+        Ok(outcome)
+    }
+
+    // Timers count down at 60 Hz, call this once per frame
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1
+        };
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1
+        };
+    }
+
+    // One 60 Hz frame: up to instructions_per_frame instructions, then a timer tick.
+    // The frame ends early when the program halts or waits for a key
+    pub fn run_frame(&mut self, instructions_per_frame: usize) -> Result<StepOutcome, CpuError> {
+        self.frame(instructions_per_frame, true)
+    }
+
+    // A watchpoint hit does not end the frame early here. Movies record and replay with it,
+    // so a frame runs the same instructions whether watchpoints are set or not
+    pub fn run_frame_ignoring_watchpoints(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, CpuError> {
+        self.frame(instructions_per_frame, false)
+    }
+
+    fn frame(
+        &mut self,
+        instructions_per_frame: usize,
+        stop_on_watchpoint: bool,
+    ) -> Result<StepOutcome, CpuError> {
+        let mut outcome = StepOutcome::Executed;
+        for _ in 0..instructions_per_frame {
+            outcome = match self.run_next_instruction()? {
+                StepOutcome::Watchpoint(_) if !stop_on_watchpoint => StepOutcome::Executed,
+                outcome => outcome,
+            };
+            if outcome != StepOutcome::Executed {
+                break;
+            }
+        }
+        self.tick_timers();
+        Ok(outcome)
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<StepOutcome, CpuError> {
+        let mut outcome = StepOutcome::Executed;
+        match instruction {
+            Instruction::ScrollDown(n) => self.scroll(0, self.scroll_distance(n as isize)),
+            Instruction::ScrollUp(n) => self.scroll(0, -self.scroll_distance(n as isize)),
+            Instruction::ClearScreen => self.screen_clear(),
+            Instruction::Return => self.return_from_subroutine()?,
+            Instruction::ScrollRight => self.scroll(self.scroll_distance(4), 0),
+            Instruction::ScrollLeft => self.scroll(-self.scroll_distance(4), 0),
+            Instruction::Exit => outcome = self.exit(),
+            Instruction::LowRes => self.set_resolution(false),
+            Instruction::HighRes => self.set_resolution(true),
+            Instruction::Jump(nnn) => self.jump_to_subroutine(nnn),
+            Instruction::Call(nnn) => self.call_subroutine(nnn)?,
+            Instruction::SkipIfEqual(x, kk) => self.skip_if_x(x, kk),
+            Instruction::SkipIfNotEqual(x, kk) => self.skip_if_not_x(x, kk),
+            Instruction::SkipIfRegistersEqual(x, y) => self.skip_if_x_eq_y(x, y),
+            Instruction::SaveRange(x, y) => self.store_x_to_y_to_memory(x, y)?,
+            Instruction::LoadRange(x, y) => self.read_memory_to_x_to_y(x, y)?,
+            Instruction::Store(x, kk) => self.store_to_x(x, kk),
+            Instruction::Add(x, kk) => self.add_to_x(x, kk),
+            Instruction::Copy(x, y) => self.store_y_to_x(x, y),
+            Instruction::Or(x, y) => self.set_x_xory(x, y),
+            Instruction::And(x, y) => self.set_x_xandy(x, y),
+            Instruction::Xor(x, y) => self.set_x_xxory(x, y),
+            Instruction::AddRegister(x, y) => self.add_y_to_x(x, y),
+            Instruction::Sub(x, y) => self.sub_y_from_x(x, y),
+            Instruction::ShiftRight(x, y) => self.right_shift_x(x, y),
+            Instruction::SubReverse(x, y) => self.sub_x_from_y(x, y),
+            Instruction::ShiftLeft(x, y) => self.left_shift_x(x, y),
+            Instruction::SkipIfRegistersNotEqual(x, y) => self.comparte_x_y(x, y),
+            Instruction::LoadI(nnn) => self.store_addres(nnn),
+            Instruction::JumpWithOffset(nnn) => self.jump_to_addr_and_v0(nnn),
+            Instruction::Random(x, kk) => self.store_rand_to_x(x, kk),
+            Instruction::Draw(x, y, n) => self.draw_a_sprite(x, y, n)?,
+            Instruction::SkipIfPressed(x) => self.skip_if_pressed(x),
+            Instruction::SkipIfNotPressed(x) => self.skip_if_not_pressed(x),
+            Instruction::LoadILong => self.store_long_addres()?,
+            Instruction::SelectPlane(x) => self.select_plane(x),
+            Instruction::LoadAudioPattern => self.load_audio_pattern()?,
+            Instruction::StoreDelay(x) => self.store_delayt_to_x(x),
+            Instruction::WaitForKey(x) => outcome = self.wait_for_press(x),
+            Instruction::SetDelay(x) => self.set_delayt(x),
+            Instruction::SetSound(x) => self.set_soundt(x),
+            Instruction::AddToI(x) => self.add_x_to_i(x),
+            Instruction::LoadFont(x) => self.set_i_to_sprite_addr(x),
+            Instruction::LoadBigFont(x) => self.set_i_to_big_sprite_addr(x),
+            Instruction::StoreBcd(x) => self.bcd_from_x_to_i(x)?,
+            Instruction::SetPitch(x) => self.set_pitch(x),
+            Instruction::SaveRegisters(x) => self.store_registers_to_memory(x)?,
+            Instruction::LoadRegisters(x) => self.read_memory_to_registers(x)?,
+            Instruction::SaveFlags(x) => self.store_registers_to_flags(x),
+            Instruction::LoadFlags(x) => self.read_flags_to_registers(x),
+        }
+        Ok(outcome)
+    }
+    // 5XY2/5XY3:
+    // VX..VY go to/from memory at I in the given order, even if X > Y.
+    // I itself is left untouched
+    fn register_range(x: u8, y: u8) -> impl Iterator<Item = (usize, usize)> {
+        let count = (x as isize - y as isize).unsigned_abs() + 1;
+        (0..count).map(move |offset| {
+            if x <= y {
+                (x as usize + offset, offset)
+            } else {
+                (x as usize - offset, offset)
+            }
+        })
+    }
+    fn store_x_to_y_to_memory(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, Cpu::register_range(x, y).count(), Access::Write)?;
+        for (register, offset) in Cpu::register_range(x, y) {
+            self.memory[memory.start + offset] = self.register[register];
+        }
+        Ok(())
+    }
+    fn read_memory_to_x_to_y(&mut self, x: u8, y: u8) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, Cpu::register_range(x, y).count(), Access::Read)?;
+        for (register, offset) in Cpu::register_range(x, y) {
+            self.register[register] = self.memory[memory.start + offset];
+        }
+        Ok(())
+    }
+    fn store_long_addres(&mut self) -> Result<(), CpuError> {
+        self.program_counter_increase();
+        self.i = self.next_opcode()?;
+        self.watch(
+            self.program_counter..self.program_counter + 2,
+            Access::Execute,
+        );
+        Ok(())
+    }
+    fn select_plane(&mut self, x: u8) {
+        self.plane = x & 0b11;
+    }
+    fn load_audio_pattern(&mut self) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, 16, Access::Read)?;
+        let mut pattern = [0; 16];
+        pattern.clone_from_slice(&self.memory[memory]);
+        self.audio_pattern = Some(pattern);
+        Ok(())
+    }
+    fn set_pitch(&mut self, x: u8) {
+        self.pitch = self.register[x as usize];
+    }
+    fn read_flags_to_registers(&mut self, x: u8) {
+        let x = x as usize;
+        self.register[..=x].clone_from_slice(&self.rpl_flags[..=x]);
+    }
+    fn store_registers_to_flags(&mut self, x: u8) {
+        let x = x as usize;
+        self.rpl_flags[..=x].clone_from_slice(&self.register[..=x]);
+    }
+    fn read_memory_to_registers(&mut self, x: u8) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, x as usize + 1, Access::Read)?;
+        for j in 0..x + 1 {
+            self.register[j as usize] = self.memory[memory.start + j as usize];
+        }
+        if self.quirks.load_store_increments_i {
+            self.i = self.i.wrapping_add(x as u16 + 1);
+        }
+        Ok(())
+    }
+    fn store_registers_to_memory(&mut self, x: u8) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, x as usize + 1, Access::Write)?;
+        for i in 0..x + 1 {
+            self.memory[memory.start + i as usize] = self.register[i as usize];
+        }
+        if self.quirks.load_store_increments_i {
+            self.i = self.i.wrapping_add(x as u16 + 1);
+        }
+        Ok(())
+    }
+    fn bcd_from_x_to_i(&mut self, x: u8) -> Result<(), CpuError> {
+        let memory = self.memory_at_i(0, 3, Access::Write)?;
+        let decimal = self.register[x as usize];
+        let i1 = decimal / 100; //maybe there is a better way of converting to bcd, but I came up with this my self. Idk if I shloud be proud or ashamed.
+        let i2 = (decimal - i1 * 100) / 10;
+        let i3 = (decimal - i1 * 100) - i2 * 10;
+
+        self.memory[memory].clone_from_slice(&[i1, i2, i3]);
+        Ok(())
+    }
+    fn set_i_to_sprite_addr(&mut self, x: u8) {
+        let vx = self.register[x as usize];
+        let sprite_length = 5;
+        self.i = vx as u16 * sprite_length;
+    }
+    fn set_i_to_big_sprite_addr(&mut self, x: u8) {
+        let vx = self.register[x as usize] & 0xF;
+        let sprite_length = 10;
+        self.i = (SCHIP_FONT_START + vx as usize * sprite_length) as u16;
+    }
+    fn add_x_to_i(&mut self, x: u8) {
+        self.i = self.i.wrapping_add(self.register[x as usize] as u16);
+    }
+    fn set_soundt(&mut self, x: u8) {
+        let vx = self.register[x as usize];
+        self.sound_timer = vx;
+    }
+    fn set_delayt(&mut self, x: u8) {
+        let vx = self.register[x as usize];
+        self.delay_timer = vx;
+    }
+    // FX0A:
+    // if there is a pressed key, write it's value to the register VX
+    // Otherwise decrease program_counter to wait for a key press
+    fn wait_for_press(&mut self, x: u8) -> StepOutcome {
+        match self.what_key_is_pressed() {
+            Some(key) => {
+                self.register[x as usize] = key;
+                StepOutcome::Executed
+            }
+            None => {
+                self.program_counter_decrease();
+                StepOutcome::WaitingForKey
+            }
+        }
+    }
+    fn store_delayt_to_x(&mut self, x: u8) {
+        self.register[x as usize] = self.delay_timer;
+    }
+    fn skip_if_not_pressed(&mut self, x: u8) {
+        let key_wanted = self.register[x as usize] as usize & 0xF;
+        if !self.keypad[key_wanted] {
+            self.skip_next_instruction();
+        }
+    }
+    fn skip_if_pressed(&mut self, x: u8) {
+        let key_wanted = self.register[x as usize] as usize & 0xF;
+        if self.keypad[key_wanted] {
+            self.skip_next_instruction();
+        }
+    }
+    // DXYN:
+    // the starting position always wraps around the screen,
+    // the rest of the sprite either wraps as well or gets clipped at the edge.
+    // DXY0 draws a 16x16 sprite made of two bytes per row, or 8x16 in low resolution under the
+    // schip_lores quirk.
+    // Every selected plane gets its own copy of the sprite data, one after another
+    fn draw_a_sprite(&mut self, x: u8, y: u8, n: u8) -> Result<(), CpuError> {
+        let (width, height) = self.resolution();
+        let x = self.register[x as usize] as usize % width;
+        let y = self.register[y as usize] as usize % height;
+        let (rows, row_bytes) = match n {
+            0 if self.quirks.schip_lores && !self.hires => (16, 1),
+            0 => (16, 2),
+            n => (n as usize, 1),
+        };
+        let planes = self.plane.count_ones() as usize;
+        let mut sprite = self
+            .memory_at_i(0, rows * row_bytes * planes, Access::Read)?
+            .start;
+
+        self.register[0xF] = 0;
+        for plane in [0b01, 0b10] {
+            if self.plane & plane == 0 {
+                continue;
+            }
+            for row in 0..rows {
+                let y = y + row;
+                if self.quirks.clip_sprites && y >= height {
+                    break;
+                }
+                let y = y % height;
+                for bit in 0..row_bytes * 8 {
+                    let x = x + bit;
+                    if self.quirks.clip_sprites && x >= width {
+                        break;
+                    }
+                    let x = x % width;
+                    let byte = self.memory[sprite + row * row_bytes + bit / 8];
+                    if (byte >> (7 - bit % 8)) & 1 == 1 {
+                        if self.pixels[y][x] & plane != 0 {
+                            self.register[0x0F] = 1;
+                        }
+                        self.pixels[y][x] ^= plane;
+                    }
+                }
+            }
+            sprite += rows * row_bytes;
+        }
+        Ok(())
+    }
+    fn store_rand_to_x(&mut self, x: u8, kk: u8) {
+        let rand_num = self.random.next_byte();
+        self.register[x as usize] = rand_num & kk;
+    }
+    fn jump_to_addr_and_v0(&mut self, nnn: u16) {
+        let offset = if self.quirks.jump_uses_vx {
+            self.register[(nnn >> 8) as usize] as u16
+        } else {
+            self.register[0] as u16
+        };
+        self.jump_to_subroutine(nnn + offset);
+    }
+    fn store_addres(&mut self, nnn: u16) {
+        self.i = nnn;
+    }
+    fn comparte_x_y(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        if vx != vy {
+            self.skip_next_instruction();
+        }
+    }
+    fn left_shift_x(&mut self, x: u8, y: u8) {
+        let value = if self.quirks.shift_uses_vy {
+            self.register[y as usize]
+        } else {
+            self.register[x as usize]
+        };
+        self.register[x as usize] = value << 1;
+        self.register[0xF] = (value & 0b1000_0000) >> 7;
+    }
+    fn sub_x_from_y(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        // no borrow when both are equal, VF is written last so it wins when X is F
+        self.register[x as usize] = vy.wrapping_sub(vx);
+        self.register[0xF] = if vy >= vx { 1 } else { 0 };
+    }
+    fn right_shift_x(&mut self, x: u8, y: u8) {
+        let value = if self.quirks.shift_uses_vy {
+            self.register[y as usize]
+        } else {
+            self.register[x as usize]
+        };
+        self.register[x as usize] = value >> 1;
+        self.register[0xF] = value & 0b0000_0001;
+    }
+    fn sub_y_from_x(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        self.register[x as usize] = vx.wrapping_sub(vy);
+        self.register[0xF] = if vx >= vy { 1 } else { 0 };
+    }
+    fn add_y_to_x(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        let (vx, carry) = vx.overflowing_add(vy);
+        self.register[x as usize] = vx;
+
+        self.register[0xF] = if carry { 1 } else { 0 };
+    }
+
+    fn set_x_xxory(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        self.register[x as usize] = vx ^ vy;
+        if self.quirks.logic_resets_vf {
+            self.register[0xF] = 0;
+        }
+    }
+
+    fn set_x_xandy(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        self.register[x as usize] = vx & vy;
+        if self.quirks.logic_resets_vf {
+            self.register[0xF] = 0;
+        }
+    }
+
+    fn set_x_xory(&mut self, x: u8, y: u8) {
+        let vx = self.register[x as usize];
+        let vy = self.register[y as usize];
+
+        self.register[x as usize] = vx | vy;
+        if self.quirks.logic_resets_vf {
+            self.register[0xF] = 0;
+        }
+    }
+
+    fn store_y_to_x(&mut self, x: u8, y: u8) {
+        self.register[x as usize] = self.register[y as usize];
+    }
+
+    fn add_to_x(&mut self, x: u8, kk: u8) {
+        let vx = self.register[x as usize];
+        let (vx, _) = vx.overflowing_add(kk);
+        self.register[x as usize] = vx;
+    }
+
+    fn store_to_x(&mut self, x: u8, kk: u8) {
+        self.register[x as usize] = kk;
+    }
+
+    fn skip_if_x_eq_y(&mut self, x: u8, y: u8) {
+        if self.register[x as usize] == self.register[y as usize] {
+            self.skip_next_instruction();
+        }
+    }
+
+    fn skip_if_not_x(&mut self, x: u8, kk: u8) {
+        if self.register[x as usize] != kk {
+            self.skip_next_instruction();
+        }
+    }
+
+    fn skip_if_x(&mut self, x: u8, kk: u8) {
+        if self.register[x as usize] == kk {
+            self.skip_next_instruction();
+        }
+    }
+
+    fn return_from_subroutine(&mut self) -> Result<(), CpuError> {
+        if self.stack_pointer == 0 {
+            return Err(CpuError::StackUnderflow {
+                address: self.program_counter,
+            });
+        }
+        self.stack_pointer -= 1;
+        self.program_counter = self.stack[self.stack_pointer as usize] as usize;
+        Ok(())
+    }
+
+    fn call_subroutine(&mut self, nnn: u16) -> Result<(), CpuError> {
+        if self.stack_pointer as usize >= self.stack.len() {
+            return Err(CpuError::StackOverflow {
+                address: self.program_counter,
+            });
+        }
+
+        self.stack[self.stack_pointer as usize] = self.program_counter as u16;
+        self.stack_pointer += 1;
+        self.jump_to_subroutine(nnn);
+        Ok(())
+    }
+
+    fn screen_clear(&mut self) {
+        for pixel in self.pixels.iter_mut().flatten() {
+            *pixel &= !self.plane;
+        }
+    }
+
+    // SUPER-CHIP 1.1 scrolled by high resolution pixels even in low resolution, half a pixel
+    // there ends up as no scroll at all
+    fn scroll_distance(&self, pixels: isize) -> isize {
+        if self.quirks.schip_lores && !self.hires {
+            pixels / 2
+        } else {
+            pixels
+        }
+    }
+
+    // 00CN/00DN/00FB/00FC:
+    // moves the selected planes by dx, dy pixels of the current resolution
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = self.resolution();
+        let old = self.pixels;
+        for y in 0..height {
+            for x in 0..width {
+                let from_x = x as isize - dx;
+                let from_y = y as isize - dy;
+                let moved = if (0..width as isize).contains(&from_x)
+                    && (0..height as isize).contains(&from_y)
+                {
+                    old[from_y as usize][from_x as usize]
+                } else {
+                    0
+                };
+                self.pixels[y][x] = (old[y][x] & !self.plane) | (moved & self.plane);
+            }
+        }
+    }
+
+    fn exit(&mut self) -> StepOutcome {
+        self.halted = true;
+        StepOutcome::Halted
+    }
+
+    fn set_resolution(&mut self, hires: bool) {
+        self.hires = hires;
+        self.pixels = [[0; HIRES_WIDTH]; HIRES_HEIGHT];
+    }
+
+    fn jump_to_subroutine(&mut self, nnn: u16) {
+        self.program_counter = nnn as usize;
+        self.program_counter_decrease();
+    }
+}
+
+#[cfg(test)]
+#[path = "./processor_test.rs"]
+mod test;
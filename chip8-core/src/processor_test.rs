@@ -0,0 +1,618 @@
+use super::*;
+
+#[test]
+fn load_rom_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&[55, 55, 55]);
+
+    assert_eq!(cpu.memory[0x200], 55);
+    assert_eq!(cpu.memory[0x201], 55);
+    assert_eq!(cpu.memory[0x202], 55);
+}
+#[test]
+fn write_memory_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.write_memory(0x1FF, &[3]);
+    assert_eq!(cpu.memory[0x1FF], 3);
+    cpu.write_memory(RAM - 1, &[1, 2]);
+    assert_eq!(cpu.memory[RAM - 1], 1);
+    cpu.write_memory(RAM + 1, &[1]);
+}
+#[test]
+fn read_memory_to_registers_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.i = 0x300;
+    for j in 0..cpu.register.len() {
+        cpu.memory[0x300 + j] = j as u8;
+    }
+    cpu.read_memory_to_registers(15).unwrap();
+    for j in 0..cpu.register.len() {
+        assert_eq!(cpu.register[j], j as u8);
+    }
+}
+#[test]
+fn store_registers_to_memory_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.i = 0x300;
+    for j in 0..cpu.register.len() {
+        cpu.register[j] = j as u8;
+    }
+    cpu.store_registers_to_memory(15).unwrap();
+    for j in 0..cpu.register.len() {
+        assert_eq!(cpu.register[j], cpu.memory[0x300 + j]);
+    }
+}
+#[test]
+fn bcd_from_x_to_i_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.i = 0x300;
+    cpu.register[0] = 159;
+    cpu.bcd_from_x_to_i(0).unwrap();
+    assert_eq!(cpu.memory[0x300], 1);
+    assert_eq!(cpu.memory[0x301], 5);
+    assert_eq!(cpu.memory[0x302], 9);
+}
+#[test]
+fn set_i_to_sprite_addr_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 0xD;
+    cpu.set_i_to_sprite_addr(0);
+    assert_eq!(cpu.i, 65);
+}
+#[test]
+fn store_rand_to_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    for i in 0..255 {
+        cpu.store_rand_to_x(0, i);
+        assert_eq!(cpu.register[0] & !i, 0);
+    }
+}
+#[test]
+fn store_rand_to_x_seeded_test() {
+    let mut first = Cpu::new(Quirks::default());
+    let mut second = Cpu::new(Quirks::default());
+    first.set_random(Random::new(42));
+    second.set_random(Random::new(42));
+    for _ in 0..64 {
+        first.store_rand_to_x(0, 0xFF);
+        second.store_rand_to_x(0, 0xFF);
+        assert_eq!(first.register[0], second.register[0]);
+    }
+    second.reset();
+    assert_eq!(first.random(), second.random());
+}
+#[test]
+fn left_shift_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.memory[0x200] = 0x80;
+    cpu.memory[0x201] = 0x0E;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.register[0xF], 0);
+    assert_eq!(cpu.register[0], 10);
+}
+#[test]
+fn sub_x_from_y_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.register[1] = 7;
+    cpu.sub_x_from_y(0, 1);
+    assert_eq!(cpu.register[0], 2);
+    assert_eq!(cpu.register[0xF], 1);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.register[1] = 4;
+    cpu.sub_x_from_y(0, 1);
+    assert_eq!(cpu.register[0], 255);
+    assert_eq!(cpu.register[0xF], 0);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.register[1] = 5;
+    cpu.sub_x_from_y(0, 1);
+    assert_eq!(cpu.register[0], 0);
+    assert_eq!(cpu.register[0xF], 1);
+}
+#[test]
+fn right_shift_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.right_shift_x(0, 0);
+    assert_eq!(cpu.register[0], 2);
+    assert_eq!(cpu.register[0xF], 1);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 4;
+    cpu.right_shift_x(0, 0);
+    assert_eq!(cpu.register[0], 2);
+    assert_eq!(cpu.register[0xF], 0);
+}
+#[test]
+fn sub_y_from_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 7;
+    cpu.register[1] = 5;
+    cpu.sub_y_from_x(0, 1);
+    assert_eq!(cpu.register[0], 2);
+    assert_eq!(cpu.register[0xF], 1);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 4;
+    cpu.register[1] = 5;
+    cpu.sub_y_from_x(0, 1);
+    assert_eq!(cpu.register[0], 255);
+    assert_eq!(cpu.register[0xF], 0);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 5;
+    cpu.register[1] = 5;
+    cpu.sub_y_from_x(0, 1);
+    assert_eq!(cpu.register[0], 0);
+    assert_eq!(cpu.register[0xF], 1);
+}
+#[test]
+fn add_y_to_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = u8::MAX;
+    cpu.register[1] = 2;
+    cpu.add_y_to_x(0, 1);
+    assert_eq!(cpu.register[0], 1);
+    assert_eq!(cpu.register[0xF], 1);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0] = 2;
+    cpu.register[1] = 2;
+    cpu.add_y_to_x(0, 1);
+    assert_eq!(cpu.register[0], 4);
+    assert_eq!(cpu.register[0xF], 0);
+}
+#[test]
+fn set_x_xxory() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 3;
+    let y = 7;
+    cpu.register[x as usize] = x;
+    cpu.register[y as usize] = y;
+    cpu.set_x_xxory(x, y);
+    assert_eq!(cpu.register[x as usize], 0b100);
+}
+#[test]
+fn set_x_xandy() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 3;
+    let y = 5;
+    cpu.register[x as usize] = x;
+    cpu.register[y as usize] = y;
+    cpu.set_x_xandy(x, y);
+    assert_eq!(cpu.register[x as usize], 0b001);
+}
+#[test]
+fn set_x_xory() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 2;
+    let y = 5;
+    cpu.register[x as usize] = x;
+    cpu.register[y as usize] = y;
+    cpu.set_x_xory(x, y);
+    assert_eq!(cpu.register[x as usize], 0b111);
+}
+#[test]
+fn skip_if_x_eq_y() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x: u8 = 5;
+    let y: u8 = 5;
+    cpu.register[x as usize] = x;
+    cpu.register[y as usize] = y;
+    cpu.skip_if_x_eq_y(x, y);
+    assert_eq!(cpu.program_counter, 0x202);
+    let mut cpu = Cpu::new(Quirks::default());
+    let x: u8 = 5;
+    let y: u8 = 6;
+    cpu.register[x as usize] = x;
+    cpu.register[y as usize] = y;
+    cpu.skip_if_x_eq_y(x, y);
+    assert_eq!(cpu.program_counter, 0x200);
+}
+#[test]
+fn skip_if_not_x() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 5;
+    cpu.register[x] = 5;
+    cpu.skip_if_not_x(x as u8, 5);
+    assert_eq!(cpu.program_counter, 0x200);
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 5;
+    cpu.register[x] = 5;
+    cpu.skip_if_not_x(x as u8, 6);
+    assert_eq!(cpu.program_counter, 0x202);
+}
+#[test]
+fn skip_if_x_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 5;
+    cpu.register[x] = 5;
+    cpu.skip_if_x(x as u8, 5);
+    assert_eq!(cpu.program_counter, 0x202);
+    let mut cpu = Cpu::new(Quirks::default());
+    let x = 5;
+    cpu.register[x] = 5;
+    cpu.skip_if_x(x as u8, 6);
+    assert_eq!(cpu.program_counter, 0x200);
+}
+#[test]
+fn call_and_return_subroutine_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    //call subroutine
+    cpu.memory[0x200] = 0x24;
+    cpu.memory[0x201] = 0x00;
+    //return from subroutine
+    cpu.memory[0x400] = 0x00;
+    cpu.memory[0x401] = 0xEE;
+
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x400);
+    assert_eq!(cpu.stack_pointer, 1);
+    assert_eq!(cpu.stack[0], 0x200);
+
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x202);
+    assert_eq!(cpu.stack_pointer, 0);
+}
+#[test]
+fn return_from_subroutine_test_underflow() {
+    let mut cpu = Cpu::new(Quirks::default());
+    assert_eq!(
+        cpu.return_from_subroutine(),
+        Err(CpuError::StackUnderflow { address: 0x200 })
+    );
+}
+#[test]
+fn call_subroutine_test_overflow_case() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.stack_pointer = 16;
+    assert_eq!(
+        cpu.call_subroutine(0x300),
+        Err(CpuError::StackOverflow { address: 0x200 })
+    );
+}
+#[test]
+fn call_subroutine_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0x23;
+    cpu.memory[0x202] = 0x00;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x300);
+    assert_eq!(cpu.stack[0], 0x200);
+    assert_eq!(cpu.stack_pointer, 1);
+}
+#[test]
+fn jump_to_subroutine_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0x13;
+    cpu.memory[0x202] = 0x00;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x300);
+}
+#[test]
+fn next_opcode_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0x15;
+    cpu.memory[0x201] = 0xFC;
+    assert_eq!(cpu.next_opcode(), Ok(0x15FC));
+}
+#[test]
+fn shift_uses_vy_quirk_test() {
+    let mut cpu = Cpu::new(Quirks::cosmac_vip());
+    cpu.register[0] = 1;
+    cpu.register[1] = 0b1000_0001;
+    cpu.right_shift_x(0, 1);
+    assert_eq!(cpu.register[0], 0b0100_0000);
+    assert_eq!(cpu.register[0xF], 1);
+    cpu.left_shift_x(0, 1);
+    assert_eq!(cpu.register[0], 0b0000_0010);
+    assert_eq!(cpu.register[0xF], 1);
+}
+#[test]
+fn shift_sets_vf_last_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0xF] = 0b0000_0011;
+    cpu.right_shift_x(0xF, 0);
+    assert_eq!(cpu.register[0xF], 1);
+}
+#[test]
+fn load_store_increments_i_quirk_test() {
+    let mut cpu = Cpu::new(Quirks::cosmac_vip());
+    cpu.i = 0x300;
+    cpu.store_registers_to_memory(3).unwrap();
+    assert_eq!(cpu.i, 0x304);
+    cpu.read_memory_to_registers(1).unwrap();
+    assert_eq!(cpu.i, 0x306);
+}
+#[test]
+fn jump_uses_vx_quirk_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.register[0] = 0x10;
+    cpu.register[3] = 0x02;
+    cpu.memory[0x200] = 0xB3;
+    cpu.memory[0x201] = 0x00;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x302);
+}
+#[test]
+fn logic_resets_vf_quirk_test() {
+    let mut cpu = Cpu::new(Quirks::cosmac_vip());
+    cpu.register[0xF] = 1;
+    cpu.set_x_xory(0, 1);
+    assert_eq!(cpu.register[0xF], 0);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.register[0xF] = 1;
+    cpu.set_x_xory(0, 1);
+    assert_eq!(cpu.register[0xF], 1);
+}
+#[test]
+fn draw_a_sprite_clip_and_wrap_test() {
+    let mut cpu = Cpu::new(Quirks::cosmac_vip());
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0xFF;
+    cpu.register[0] = WIDTH as u8 - 4;
+    cpu.draw_a_sprite(0, 1, 1).unwrap();
+    assert_eq!(cpu.pixels[0][WIDTH - 1], 1);
+    assert_eq!(cpu.pixels[0][0], 0);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0xFF;
+    cpu.register[0] = WIDTH as u8 - 4;
+    cpu.draw_a_sprite(0, 1, 1).unwrap();
+    assert_eq!(cpu.pixels[0][WIDTH - 1], 1);
+    assert_eq!(cpu.pixels[0][3], 1);
+}
+#[test]
+fn set_resolution_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.pixels[0][0] = 1;
+    cpu.memory[0x200] = 0x00;
+    cpu.memory[0x201] = 0xFF;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.resolution(), (HIRES_WIDTH, HIRES_HEIGHT));
+    assert_eq!(cpu.pixels[0][0], 0);
+    cpu.memory[0x202] = 0x00;
+    cpu.memory[0x203] = 0xFE;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.resolution(), (WIDTH, HEIGHT));
+}
+#[test]
+fn draw_a_big_sprite_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.set_resolution(true);
+    cpu.i = 0x300;
+    for j in 0..32 {
+        cpu.memory[0x300 + j] = 0xFF;
+    }
+    cpu.register[0] = 100;
+    cpu.register[1] = 40;
+    cpu.draw_a_sprite(0, 1, 0).unwrap();
+    assert_eq!(cpu.pixels[40][100], 1);
+    assert_eq!(cpu.pixels[55][115], 1);
+    assert_eq!(cpu.pixels[56][115], 0);
+    assert_eq!(cpu.pixels[55][116], 0);
+    assert_eq!(cpu.register[0xF], 0);
+}
+#[test]
+fn scroll_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.pixels[0][0] = 1;
+    cpu.scroll(0, 3);
+    assert_eq!(cpu.pixels[0][0], 0);
+    assert_eq!(cpu.pixels[3][0], 1);
+    cpu.scroll(4, 0);
+    assert_eq!(cpu.pixels[3][4], 1);
+    cpu.scroll(-4, 0);
+    cpu.scroll(-4, 0);
+    assert_eq!(cpu.pixels[3][0], 0);
+    assert!(cpu.pixels.iter().flatten().all(|pixel| *pixel == 0));
+}
+#[test]
+fn schip_lores_quirk_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.i = 0x300;
+    for j in 0..32 {
+        cpu.memory[0x300 + j] = 0xFF;
+    }
+    cpu.draw_a_sprite(0, 0, 0).unwrap();
+    assert_eq!(cpu.pixels[15][7], 1);
+    assert_eq!(cpu.pixels[15][8], 0);
+    assert_eq!(cpu.pixels[16][7], 0);
+    // 00C4 and 00FB move by 2 low resolution pixels, half of what they ask for
+    cpu.memory[0x200..0x204].copy_from_slice(&[0x00, 0xC4, 0x00, 0xFB]);
+    cpu.run_next_instruction().unwrap();
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.pixels[2][2], 1);
+    assert_eq!(cpu.pixels[1][2], 0);
+    assert_eq!(cpu.pixels[2][1], 0);
+    // high resolution is the same either way
+    cpu.set_resolution(true);
+    cpu.draw_a_sprite(0, 0, 0).unwrap();
+    assert_eq!(cpu.pixels[15][15], 1);
+    cpu.memory[0x204..0x206].copy_from_slice(&[0x00, 0xFB]);
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.pixels[0][4], 1);
+    assert_eq!(cpu.pixels[0][3], 0);
+
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    cpu.i = 0x300;
+    cpu.memory[0x300..0x320].copy_from_slice(&[0xFF; 32]);
+    cpu.draw_a_sprite(0, 0, 0).unwrap();
+    assert_eq!(cpu.pixels[15][15], 1);
+    cpu.memory[0x200..0x202].copy_from_slice(&[0x00, 0xC4]);
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.pixels[4][0], 1);
+    assert_eq!(cpu.pixels[3][0], 0);
+}
+#[test]
+fn set_i_to_big_sprite_addr_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.register[0] = 2;
+    cpu.set_i_to_big_sprite_addr(0);
+    assert_eq!(cpu.i, 100);
+    assert_eq!(cpu.memory[100], 0x3E);
+}
+#[test]
+fn store_and_read_flags_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    for j in 0..8 {
+        cpu.register[j] = j as u8 + 1;
+    }
+    cpu.store_registers_to_flags(7);
+    cpu.register = [0; 16];
+    cpu.read_flags_to_registers(3);
+    assert_eq!(cpu.register[..5], [1, 2, 3, 4, 0]);
+}
+#[test]
+fn exit_test() {
+    let mut cpu = Cpu::new(Quirks::super_chip());
+    cpu.memory[0x200] = 0x00;
+    cpu.memory[0x201] = 0xFD;
+    cpu.run_next_instruction().unwrap();
+    assert!(cpu.halted);
+    let program_counter = cpu.program_counter;
+    assert_eq!(cpu.run_next_instruction(), Ok(StepOutcome::Halted));
+    assert_eq!(cpu.program_counter, program_counter);
+}
+#[test]
+fn store_long_addres_test() {
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    cpu.memory[0x200..0x204].clone_from_slice(&[0xF0, 0x00, 0x12, 0x34]);
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.i, 0x1234);
+    assert_eq!(cpu.program_counter, 0x204);
+}
+#[test]
+fn skip_over_long_addres_test() {
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    cpu.memory[0x200..0x206].clone_from_slice(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34]);
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0x206);
+}
+#[test]
+fn store_and_read_x_to_y_test() {
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    cpu.i = 0x300;
+    cpu.register[2] = 7;
+    cpu.register[3] = 8;
+    cpu.register[4] = 9;
+    cpu.store_x_to_y_to_memory(2, 4).unwrap();
+    assert_eq!(cpu.memory[0x300..0x303], [7, 8, 9]);
+    cpu.store_x_to_y_to_memory(4, 2).unwrap();
+    assert_eq!(cpu.memory[0x300..0x303], [9, 8, 7]);
+    cpu.read_memory_to_x_to_y(5, 6).unwrap();
+    assert_eq!(cpu.register[5..7], [9, 8]);
+    assert_eq!(cpu.i, 0x300);
+}
+#[test]
+fn draw_a_sprite_on_planes_test() {
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0x80;
+    cpu.memory[0x301] = 0xC0;
+    cpu.select_plane(3);
+    cpu.draw_a_sprite(0, 0, 1).unwrap();
+    assert_eq!(cpu.pixels[0][0], 0b11);
+    assert_eq!(cpu.pixels[0][1], 0b10);
+    cpu.select_plane(2);
+    cpu.screen_clear();
+    assert_eq!(cpu.pixels[0][0], 0b01);
+    assert_eq!(cpu.pixels[0][1], 0);
+}
+#[test]
+fn load_audio_pattern_test() {
+    let mut cpu = Cpu::new(Quirks::xo_chip());
+    assert_eq!(cpu.audio_pattern(), None);
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0xAA;
+    cpu.load_audio_pattern().unwrap();
+    assert_eq!(cpu.audio_pattern().unwrap()[0], 0xAA);
+    cpu.register[1] = 112;
+    cpu.set_pitch(1);
+    assert_eq!(cpu.pitch(), 112);
+}
+#[test]
+fn unknown_opcode_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0xFF;
+    cpu.memory[0x201] = 0xFF;
+    assert_eq!(
+        cpu.run_next_instruction(),
+        Err(CpuError::UnknownOpcode {
+            opcode: 0xFFFF,
+            address: 0x200
+        })
+    );
+}
+#[test]
+fn memory_out_of_range_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.i = (RAM - 2) as u16;
+    assert_eq!(
+        cpu.bcd_from_x_to_i(0),
+        Err(CpuError::MemoryOutOfRange {
+            i: (RAM - 2) as u16,
+            offset: 2,
+            address: 0x200
+        })
+    );
+    cpu.i = 0xFFFF;
+    assert!(cpu.draw_a_sprite(0, 0, 2).is_err());
+    assert!(cpu.store_registers_to_memory(1).is_err());
+}
+#[test]
+fn program_counter_out_of_range_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.program_counter = RAM - 1;
+    assert_eq!(
+        cpu.run_next_instruction(),
+        Err(CpuError::ProgramCounterOutOfRange { address: RAM - 1 })
+    );
+}
+#[test]
+fn wait_for_press_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0xF3;
+    cpu.memory[0x201] = 0x0A;
+    assert_eq!(cpu.run_next_instruction(), Ok(StepOutcome::WaitingForKey));
+    assert_eq!(cpu.program_counter, 0x200);
+    cpu.keypad[0xB] = true;
+    assert_eq!(cpu.run_next_instruction(), Ok(StepOutcome::Executed));
+    assert_eq!(cpu.register[3], 0xB);
+}
+#[test]
+fn jump_to_zero_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.memory[0x200] = 0x10;
+    cpu.memory[0x201] = 0x00;
+    cpu.run_next_instruction().unwrap();
+    assert_eq!(cpu.program_counter, 0);
+}
+#[test]
+fn tick_timers_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.delay_timer = 2;
+    cpu.sound_timer = 1;
+    cpu.tick_timers();
+    assert_eq!((cpu.delay_timer, cpu.sound_timer), (1, 0));
+    cpu.tick_timers();
+    cpu.tick_timers();
+    assert_eq!((cpu.delay_timer, cpu.sound_timer), (0, 0));
+}
+#[test]
+fn run_frame_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    // 0x200: V0 += 1, jump 0x200
+    cpu.load_rom(&[0x70, 0x01, 0x12, 0x00]);
+    cpu.delay_timer = 10;
+    assert_eq!(cpu.run_frame(10), Ok(StepOutcome::Executed));
+    assert_eq!(cpu.register[0], 5);
+    assert_eq!(cpu.delay_timer, 9);
+
+    // a halted program ends the frame early, the timers still tick
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&[0x70, 0x01, 0x00, 0xFD, 0x70, 0x01]);
+    cpu.delay_timer = 10;
+    assert_eq!(cpu.run_frame(10), Ok(StepOutcome::Halted));
+    assert_eq!(cpu.register[0], 1);
+    assert_eq!(cpu.delay_timer, 9);
+}
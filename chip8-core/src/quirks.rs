@@ -0,0 +1,99 @@
+// Several CHIP-8 opcodes were implemented differently by the interpreters
+// that followed the original COSMAC VIP one. Quirks select which of those
+// behaviours the Cpu follows, the default being the one this emulator always had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6/8XYE: shift VY and store the result in VX instead of shifting VX in place
+    pub shift_uses_vy: bool,
+    // FX55/FX65: leave I pointing past the last register stored or read
+    pub load_store_increments_i: bool,
+    // BNNN: jump to XNN + VX instead of NNN + V0
+    pub jump_uses_vx: bool,
+    // DXYN: cut sprites off at the screen edge instead of wrapping them around
+    pub clip_sprites: bool,
+    // 8XY1/8XY2/8XY3: set VF to 0 after the logic operation
+    pub logic_resets_vf: bool,
+    // DXY0/00CN/00DN/00FB/00FC in low resolution: draw 8x16 sprites and scroll half as far, the
+    // way SUPER-CHIP 1.1 did, instead of 16x16 sprites and whole low resolution pixels
+    pub schip_lores: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::chip8()
+    }
+}
+
+impl Quirks {
+    pub const fn chip8() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            clip_sprites: false,
+            logic_resets_vf: false,
+            schip_lores: false,
+        }
+    }
+
+    pub const fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            clip_sprites: true,
+            logic_resets_vf: true,
+            schip_lores: false,
+        }
+    }
+
+    pub const fn super_chip() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            clip_sprites: true,
+            logic_resets_vf: false,
+            schip_lores: true,
+        }
+    }
+
+    pub const fn xo_chip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            clip_sprites: false,
+            logic_resets_vf: false,
+            schip_lores: false,
+        }
+    }
+
+    // One bit per quirk, for save states and movies
+    pub fn to_bits(&self) -> u8 {
+        self.shift_uses_vy as u8
+            | (self.load_store_increments_i as u8) << 1
+            | (self.jump_uses_vx as u8) << 2
+            | (self.clip_sprites as u8) << 3
+            | (self.logic_resets_vf as u8) << 4
+            | (self.schip_lores as u8) << 5
+    }
+
+    pub fn from_bits(bits: u8) -> Quirks {
+        Quirks {
+            shift_uses_vy: bits & 1 != 0,
+            load_store_increments_i: bits & 1 << 1 != 0,
+            jump_uses_vx: bits & 1 << 2 != 0,
+            clip_sprites: bits & 1 << 3 != 0,
+            logic_resets_vf: bits & 1 << 4 != 0,
+            schip_lores: bits & 1 << 5 != 0,
+        }
+    }
+}
+
+pub const QUIRKS_PROFILES: [(&str, Quirks); 4] = [
+    ("CHIP-8", Quirks::chip8()),
+    ("COSMAC VIP", Quirks::cosmac_vip()),
+    ("SUPER-CHIP", Quirks::super_chip()),
+    ("XO-CHIP", Quirks::xo_chip()),
+];
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn same_seed_same_sequence_test() {
+    let mut first = Random::new(1234);
+    let mut second = Random::new(1234);
+    for _ in 0..100 {
+        assert_eq!(first.next_byte(), second.next_byte());
+    }
+    let mut other = Random::new(4321);
+    let first: Vec<u8> = (0..16).map(|_| first.next_byte()).collect();
+    let other: Vec<u8> = (0..16).map(|_| other.next_byte()).collect();
+    assert_ne!(first, other);
+}
+
+#[test]
+fn state_continues_sequence_test() {
+    let mut random = Random::new(7);
+    random.next_byte();
+    let mut copy = Random::new(random.state());
+    assert_eq!(random.next_byte(), copy.next_byte());
+}
+
+#[test]
+fn full_byte_range_test() {
+    let mut random = Random::new(0);
+    let mut seen = [false; 256];
+    for _ in 0..10_000 {
+        seen[random.next_byte() as usize] = true;
+    }
+    assert!(seen.iter().all(|seen| *seen));
+}
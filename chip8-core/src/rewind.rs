@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use crate::processor::Cpu;
+
+// Frame history for rewinding. Only the newest snapshot is kept whole, every older one is
+// stored as the XOR with its successor, run length encoded. Between two frames almost
+// nothing in the 64 KiB of memory changes, so a delta is usually a few dozen bytes
+pub struct Rewind {
+    deltas: VecDeque<Vec<u8>>,
+    latest: Option<Vec<u8>>,
+    frames: usize,
+}
+
+// A delta is a list of (zero bytes to skip: u16, literal count: u16, literals)
+fn encode_delta(previous: &[u8], next: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    let mut position = 0;
+    while position < next.len() {
+        let zeros_start = position;
+        while position < next.len()
+            && position - zeros_start < u16::MAX as usize
+            && previous[position] == next[position]
+        {
+            position += 1;
+        }
+        let literals_start = position;
+        while position < next.len()
+            && position - literals_start < u16::MAX as usize
+            && previous[position] != next[position]
+        {
+            position += 1;
+        }
+        delta.extend_from_slice(&((literals_start - zeros_start) as u16).to_be_bytes());
+        delta.extend_from_slice(&((position - literals_start) as u16).to_be_bytes());
+        for index in literals_start..position {
+            delta.push(previous[index] ^ next[index]);
+        }
+    }
+    delta
+}
+
+fn apply_delta(snapshot: &mut [u8], delta: &[u8]) {
+    let mut position = 0;
+    let mut rest = delta;
+    while rest.len() >= 4 {
+        let zeros = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        let literals = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+        position += zeros;
+        for (byte, change) in snapshot[position..position + literals]
+            .iter_mut()
+            .zip(&rest[4..4 + literals])
+        {
+            *byte ^= change;
+        }
+        position += literals;
+        rest = &rest[4 + literals..];
+    }
+}
+
+impl Rewind {
+    // frames is how many steps back are available, 60 per second of history
+    pub fn new(frames: usize) -> Rewind {
+        Rewind {
+            deltas: VecDeque::new(),
+            latest: None,
+            frames,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.latest = None;
+    }
+
+    // Call once per frame with the machine as it is after the frame ran
+    pub fn push(&mut self, cpu: &Cpu, rom: &[u8]) {
+        let snapshot = cpu.save_state(rom);
+        if let Some(latest) = &self.latest {
+            if self.frames == 0 {
+                self.latest = Some(snapshot);
+                return;
+            }
+            if self.deltas.len() == self.frames {
+                self.deltas.pop_front();
+            }
+            self.deltas.push_back(encode_delta(latest, &snapshot));
+        }
+        self.latest = Some(snapshot);
+    }
+
+    // Puts the Cpu one frame back, false once the history is used up
+    pub fn step_back(&mut self, cpu: &mut Cpu, rom: &[u8]) -> bool {
+        let (delta, latest) = match (self.deltas.pop_back(), self.latest.as_mut()) {
+            (Some(delta), Some(latest)) => (delta, latest),
+            _ => return false,
+        };
+        apply_delta(latest, &delta);
+        cpu.load_state(rom, latest).is_ok()
+    }
+}
+
+#[cfg(test)]
+#[path = "./rewind_test.rs"]
+mod test;
@@ -0,0 +1,54 @@
+use super::*;
+use crate::quirks::Quirks;
+
+// V0 += 1, draw the font glyph for V0, jump back
+const ROM: [u8; 8] = [0x70, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x00];
+
+#[test]
+fn delta_round_trip_test() {
+    let previous = vec![0u8; 200_000];
+    let mut next = previous.clone();
+    next[5] = 1;
+    next[6] = 2;
+    next[150_000] = 3;
+    let delta = encode_delta(&previous, &next);
+    assert!(delta.len() < 32);
+
+    let mut snapshot = next.clone();
+    apply_delta(&mut snapshot, &delta);
+    assert_eq!(snapshot, previous);
+}
+
+#[test]
+fn step_back_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    let mut rewind = Rewind::new(100);
+    let mut states = Vec::new();
+    for _ in 0..10 {
+        cpu.run_frame(3).unwrap();
+        rewind.push(&cpu, &ROM);
+        states.push(cpu.save_state(&ROM));
+    }
+    assert_eq!(rewind.len(), 9);
+
+    for expected in states.iter().rev().skip(1) {
+        assert!(rewind.step_back(&mut cpu, &ROM));
+        assert_eq!(&cpu.save_state(&ROM), expected);
+    }
+    assert!(!rewind.step_back(&mut cpu, &ROM));
+}
+
+#[test]
+fn history_limit_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    let mut rewind = Rewind::new(4);
+    for _ in 0..10 {
+        cpu.run_frame(3).unwrap();
+        rewind.push(&cpu, &ROM);
+    }
+    assert_eq!(rewind.len(), 4);
+    rewind.clear();
+    assert!(rewind.is_empty());
+}
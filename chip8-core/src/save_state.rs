@@ -0,0 +1,177 @@
+use std::fmt;
+
+use sha1_smol::Sha1;
+
+use crate::processor::Cpu;
+use crate::quirks::Quirks;
+use crate::random::Random;
+use crate::{HIRES_HEIGHT, HIRES_WIDTH, RAM};
+
+// Layout of a save state, all numbers big endian:
+//   "C8ST", version, SHA-1 of the ROM (20 bytes),
+//   memory, V0-VF, program counter (u32), stack (16 x u16), stack pointer, I (u16),
+//   delay timer, sound timer, pixels (128 x 64, one plane mask per byte), hires, plane,
+//   pitch, audio pattern present + 16 bytes, RPL flags, halted, keypad (16 bytes),
+//   quirks (one bit each), random state (u64)
+// Bump STATE_VERSION whenever this layout changes.
+const MAGIC: &[u8; 4] = b"C8ST";
+pub const STATE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    NotAState,
+    UnsupportedVersion(u8),
+    WrongRom,
+    Corrupted,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::WrongRom => write!(f, "save state belongs to another rom"),
+            StateError::Corrupted => write!(f, "save state is corrupted"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+pub fn rom_hash(rom: &[u8]) -> [u8; 20] {
+    Sha1::from(rom).digest().bytes()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::Corrupted);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn byte(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, StateError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupted),
+        }
+    }
+
+    fn word(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+}
+
+impl Cpu {
+    // The whole machine, tied to the rom it is running
+    pub fn save_state(&self, rom: &[u8]) -> Vec<u8> {
+        let mut state = Vec::with_capacity(RAM + HIRES_WIDTH * HIRES_HEIGHT + 256);
+        state.extend_from_slice(MAGIC);
+        state.push(STATE_VERSION);
+        state.extend_from_slice(&rom_hash(rom));
+        state.extend_from_slice(&self.memory);
+        state.extend_from_slice(&self.register);
+        state.extend_from_slice(&(self.program_counter as u32).to_be_bytes());
+        for address in self.stack {
+            state.extend_from_slice(&address.to_be_bytes());
+        }
+        state.push(self.stack_pointer);
+        state.extend_from_slice(&self.i.to_be_bytes());
+        state.push(self.delay_timer);
+        state.push(self.sound_timer);
+        for row in &self.pixels {
+            state.extend_from_slice(row);
+        }
+        state.push(self.hires as u8);
+        state.push(self.plane);
+        state.push(self.pitch);
+        state.push(self.audio_pattern.is_some() as u8);
+        state.extend_from_slice(&self.audio_pattern.unwrap_or([0; 16]));
+        state.extend_from_slice(&self.rpl_flags);
+        state.push(self.halted as u8);
+        for pressed in self.keypad {
+            state.push(pressed as u8);
+        }
+        state.push(self.quirks.to_bits());
+        state.extend_from_slice(&self.random.state().to_be_bytes());
+        state
+    }
+
+    // Leaves the Cpu untouched when the state can't be loaded
+    pub fn load_state(&mut self, rom: &[u8], state: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader { data: state };
+        if reader.bytes(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(StateError::NotAState);
+        }
+        let version = reader.byte()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        if reader.array::<20>()? != rom_hash(rom) {
+            return Err(StateError::WrongRom);
+        }
+
+        let mut cpu = Cpu::new(self.quirks);
+        cpu.memory.copy_from_slice(reader.bytes(RAM)?);
+        cpu.register = reader.array()?;
+        cpu.program_counter = u32::from_be_bytes(reader.array()?) as usize;
+        for address in cpu.stack.iter_mut() {
+            *address = reader.word()?;
+        }
+        cpu.stack_pointer = reader.byte()?;
+        cpu.i = reader.word()?;
+        cpu.delay_timer = reader.byte()?;
+        cpu.sound_timer = reader.byte()?;
+        for row in cpu.pixels.iter_mut() {
+            *row = reader.array()?;
+        }
+        cpu.hires = reader.bool()?;
+        cpu.plane = reader.byte()?;
+        cpu.pitch = reader.byte()?;
+        let has_audio_pattern = reader.bool()?;
+        let audio_pattern = reader.array()?;
+        cpu.audio_pattern = has_audio_pattern.then_some(audio_pattern);
+        cpu.rpl_flags = reader.array()?;
+        cpu.halted = reader.bool()?;
+        for pressed in cpu.keypad.iter_mut() {
+            *pressed = reader.bool()?;
+        }
+        cpu.quirks = Quirks::from_bits(reader.byte()?);
+        cpu.random = Random::new(u64::from_be_bytes(reader.array()?));
+
+        if !reader.data.is_empty()
+            || cpu.program_counter >= RAM
+            || cpu.stack_pointer as usize > cpu.stack.len()
+            || cpu.plane > 3
+        {
+            return Err(StateError::Corrupted);
+        }
+        cpu.watchpoints = std::mem::take(&mut self.watchpoints);
+        cpu.tracer = self.tracer.take();
+        *self = cpu;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./save_state_test.rs"]
+mod test;
@@ -0,0 +1,52 @@
+use super::*;
+
+const ROM: [u8; 6] = [0x60, 0x05, 0xC1, 0xFF, 0x12, 0x02];
+
+fn running_cpu() -> Cpu {
+    let mut cpu = Cpu::new(Quirks::cosmac_vip());
+    cpu.set_random(Random::new(99));
+    cpu.load_rom(&ROM);
+    cpu.run_frame(5).unwrap();
+    cpu.delay_timer = 30;
+    cpu.pixels[3][7] = 1;
+    cpu.audio_pattern = Some([0xAA; 16]);
+    cpu.keypad[4] = true;
+    cpu
+}
+
+#[test]
+fn save_and_load_state_test() {
+    let cpu = running_cpu();
+    let state = cpu.save_state(&ROM);
+
+    let mut loaded = Cpu::new(Quirks::default());
+    loaded.load_state(&ROM, &state).unwrap();
+    assert_eq!(loaded.save_state(&ROM), state);
+    assert_eq!(loaded.quirks, Quirks::cosmac_vip());
+    assert_eq!(loaded.random(), cpu.random());
+    assert_eq!(loaded.register[0], 5);
+    assert_eq!(loaded.pixels[3][7], 1);
+}
+
+#[test]
+fn load_state_errors_test() {
+    let mut state = running_cpu().save_state(&ROM);
+    let mut cpu = Cpu::new(Quirks::default());
+    let before = cpu.save_state(&ROM);
+
+    assert_eq!(
+        cpu.load_state(&[0x00, 0xE0], &state),
+        Err(StateError::WrongRom)
+    );
+    assert_eq!(
+        cpu.load_state(&ROM, &state[..state.len() - 1]),
+        Err(StateError::Corrupted)
+    );
+    assert_eq!(cpu.load_state(&ROM, b"nope"), Err(StateError::NotAState));
+    state[4] = STATE_VERSION + 1;
+    assert_eq!(
+        cpu.load_state(&ROM, &state),
+        Err(StateError::UnsupportedVersion(STATE_VERSION + 1))
+    );
+    assert_eq!(cpu.save_state(&ROM), before);
+}
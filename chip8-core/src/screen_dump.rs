@@ -0,0 +1,62 @@
+use crate::processor::Cpu;
+
+// The visible part of the framebuffer as ASCII art, PBM or PNG.
+// XO-CHIP pixels hold one bit per plane, PBM only tells lit from dark pixels
+const ASCII: [char; 4] = ['.', '#', '+', '*'];
+// same colours as the window
+const PALETTE: [[u8; 3]; 4] = [[0, 0, 0], [255, 255, 255], [255, 102, 0], [102, 33, 0]];
+
+fn visible_rows(cpu: &Cpu) -> impl Iterator<Item = &[u8]> {
+    let (width, height) = cpu.resolution();
+    cpu.read_pixels()
+        .iter()
+        .take(height)
+        .map(move |row| &row[..width])
+}
+
+pub fn to_ascii(cpu: &Cpu) -> String {
+    let mut ascii = String::new();
+    for row in visible_rows(cpu) {
+        ascii.extend(row.iter().map(|pixel| ASCII[*pixel as usize & 0b11]));
+        ascii.push('\n');
+    }
+    ascii
+}
+
+// Plain PBM (P1), 1 is a lit pixel
+pub fn to_pbm(cpu: &Cpu) -> String {
+    let (width, height) = cpu.resolution();
+    let mut pbm = format!("P1\n{} {}\n", width, height);
+    for row in visible_rows(cpu) {
+        let bits: Vec<&str> = row
+            .iter()
+            .map(|pixel| if *pixel != 0 { "1" } else { "0" })
+            .collect();
+        pbm.push_str(&bits.join(" "));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+pub fn to_png(cpu: &Cpu) -> Vec<u8> {
+    let (width, height) = cpu.resolution();
+    let mut data = Vec::with_capacity(width * height * 3);
+    for row in visible_rows(cpu) {
+        for pixel in row {
+            data.extend_from_slice(&PALETTE[*pixel as usize & 0b11]);
+        }
+    }
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // writing into a Vec can't fail
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&data).unwrap();
+    writer.finish().unwrap();
+    png
+}
+
+#[cfg(test)]
+#[path = "./screen_dump_test.rs"]
+mod test;
@@ -0,0 +1,35 @@
+use super::*;
+use crate::quirks::Quirks;
+
+fn cpu() -> Cpu {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.pixels[0][0] = 1;
+    cpu.pixels[1][2] = 3;
+    cpu
+}
+
+#[test]
+fn ascii_and_pbm_test() {
+    let cpu = cpu();
+    let ascii = to_ascii(&cpu);
+    let lines: Vec<&str> = ascii.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert_eq!(lines[0], format!("#{}", ".".repeat(63)));
+    assert_eq!(lines[1], format!("..*{}", ".".repeat(61)));
+
+    let pbm = to_pbm(&cpu);
+    assert!(pbm.starts_with("P1\n64 32\n1 0 0 0"));
+    assert_eq!(pbm.lines().count(), 34);
+}
+
+#[test]
+fn png_test() {
+    let png = to_png(&cpu());
+    let decoder = png::Decoder::new(png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    assert_eq!((reader.info().width, reader.info().height), (64, 32));
+    assert_eq!(&data[..6], &[255, 255, 255, 0, 0, 0]);
+    assert_eq!(&data[64 * 3 + 6..64 * 3 + 9], &[102, 33, 0]);
+}
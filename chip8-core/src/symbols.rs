@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Where the code of each source line and each label ended up in memory.
+// The assembler and the Octo compiler fill one in so debuggers can work with source lines.
+// As text every entry is a line of its own:
+//     line 12 0x0204
+//     label main 0x0200
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolMap {
+    lines: BTreeMap<usize, usize>,
+    labels: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    pub line: usize,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: bad symbol map entry", self.line)
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+impl SymbolMap {
+    pub fn new() -> SymbolMap {
+        SymbolMap::default()
+    }
+
+    // Only the first address is kept when a line emits several instructions
+    pub fn add_line(&mut self, line: usize, address: usize) {
+        self.lines.entry(line).or_insert(address);
+    }
+
+    pub fn add_label(&mut self, name: &str, address: usize) {
+        self.labels.insert(name.to_string(), address);
+    }
+
+    pub fn label_address(&self, name: &str) -> Option<usize> {
+        self.labels.get(name).copied()
+    }
+
+    // The first line at or after the given one that has code, and the address of that code
+    pub fn line_address(&self, line: usize) -> Option<(usize, usize)> {
+        self.lines
+            .range(line..)
+            .next()
+            .map(|(line, address)| (*line, *address))
+    }
+
+    // The line whose code starts closest before the address
+    pub fn line_at(&self, address: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .filter(|(_, start)| **start <= address)
+            .max_by_key(|(_, start)| **start)
+            .map(|(line, _)| *line)
+    }
+
+    pub fn label_at(&self, address: usize) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, label_address)| **label_address == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (line, address) in &self.lines {
+            text.push_str(&format!("line {} 0x{:04X}\n", line, address));
+        }
+        for (name, address) in &self.labels {
+            text.push_str(&format!("label {} 0x{:04X}\n", name, address));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<SymbolMap, SymbolError> {
+        let mut symbols = SymbolMap::new();
+        for (index, entry) in text.lines().enumerate() {
+            let fields: Vec<&str> = entry.split_whitespace().collect();
+            let address = fields
+                .get(2)
+                .and_then(|address| address.strip_prefix("0x"))
+                .and_then(|address| usize::from_str_radix(address, 16).ok());
+            match (fields.as_slice(), address) {
+                ([], _) => {}
+                (["line", line, _], Some(address)) => match line.parse() {
+                    Ok(line) => symbols.add_line(line, address),
+                    Err(_) => return Err(SymbolError { line: index + 1 }),
+                },
+                (["label", name, _], Some(address)) => symbols.add_label(name, address),
+                _ => return Err(SymbolError { line: index + 1 }),
+            }
+        }
+        Ok(symbols)
+    }
+}
+
+#[cfg(test)]
+#[path = "./symbols_test.rs"]
+mod test;
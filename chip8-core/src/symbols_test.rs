@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn lookup_test() {
+    let mut symbols = SymbolMap::new();
+    symbols.add_label("main", 0x200);
+    symbols.add_line(2, 0x200);
+    symbols.add_line(3, 0x202);
+    symbols.add_line(3, 0x204);
+    symbols.add_line(6, 0x206);
+
+    assert_eq!(symbols.line_address(3), Some((3, 0x202)));
+    assert_eq!(symbols.line_address(4), Some((6, 0x206)));
+    assert_eq!(symbols.line_address(7), None);
+    assert_eq!(symbols.line_at(0x205), Some(3));
+    assert_eq!(symbols.line_at(0x100), None);
+    assert_eq!(symbols.label_at(0x200), Some("main"));
+    assert_eq!(symbols.label_address("main"), Some(0x200));
+}
+
+#[test]
+fn text_test() {
+    let mut symbols = SymbolMap::new();
+    symbols.add_line(2, 0x200);
+    symbols.add_label("loop", 0x202);
+    let text = symbols.to_text();
+    assert_eq!(text, "line 2 0x0200\nlabel loop 0x0202\n");
+    assert_eq!(SymbolMap::from_text(&text), Ok(symbols));
+    assert_eq!(
+        SymbolMap::from_text("line 2 0x0200\nline x 0x0202"),
+        Err(SymbolError { line: 2 })
+    );
+}
@@ -0,0 +1,393 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::instruction::Instruction;
+use crate::processor::Cpu;
+
+// Execution traces, one record per executed instruction with the state after it ran.
+// Text traces have a line per record:
+//     cycle pc opcode mnemonic ; I=0300 V0=02 VF=01 FB=1A2B3C4D
+// Only the registers that changed since the previous record are listed, FB is the
+// framebuffer hash and is only listed when the framebuffer changed.
+// Binary traces start with "C8TR" and a version, then per record:
+//     cycles since the previous record (LEB128), pc, opcode, I (u16 each, big endian),
+//     framebuffer changed (u8) + hash (u32), changed register count (u8) + (register, value) pairs
+const MAGIC: &[u8; 4] = b"C8TR";
+pub const TRACE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceError {
+    UnsupportedVersion(u8),
+    Corrupted,
+    BadLine(usize),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceError::UnsupportedVersion(version) => {
+                write!(f, "unsupported trace version {}", version)
+            }
+            TraceError::Corrupted => write!(f, "trace is corrupted"),
+            TraceError::BadLine(line) => write!(f, "line {}: not a trace record", line),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub cycle: u64,
+    pub program_counter: u16,
+    pub opcode: u16,
+    pub i: u16,
+    // registers that changed since the previous record
+    pub registers: Vec<(u8, u8)>,
+    pub framebuffer: Option<u32>,
+}
+
+impl TraceRecord {
+    pub fn to_line(&self) -> String {
+        let mnemonic = Instruction::decode(self.opcode)
+            .map(|instruction| instruction.to_string())
+            .unwrap_or_else(|| "??".to_string());
+        let mut line = format!(
+            "{} {:04X} {:04X} {} ; I={:04X}",
+            self.cycle, self.program_counter, self.opcode, mnemonic, self.i
+        );
+        for (register, value) in &self.registers {
+            line.push_str(&format!(" V{:X}={:02X}", register, value));
+        }
+        if let Some(framebuffer) = self.framebuffer {
+            line.push_str(&format!(" FB={:08X}", framebuffer));
+        }
+        line
+    }
+
+    pub fn from_line(line: &str) -> Option<TraceRecord> {
+        let (instruction, state) = line.split_once(" ; ")?;
+        let mut fields = instruction.split_whitespace();
+        let mut record = TraceRecord {
+            cycle: fields.next()?.parse().ok()?,
+            program_counter: u16::from_str_radix(fields.next()?, 16).ok()?,
+            opcode: u16::from_str_radix(fields.next()?, 16).ok()?,
+            i: 0,
+            registers: Vec::new(),
+            framebuffer: None,
+        };
+        for field in state.split_whitespace() {
+            let (name, value) = field.split_once('=')?;
+            match name {
+                "I" => record.i = u16::from_str_radix(value, 16).ok()?,
+                "FB" => record.framebuffer = Some(u32::from_str_radix(value, 16).ok()?),
+                _ => {
+                    let register = u8::from_str_radix(name.strip_prefix('V')?, 16).ok()?;
+                    let value = u8::from_str_radix(value, 16).ok()?;
+                    if register > 0xF {
+                        return None;
+                    }
+                    record.registers.push((register, value));
+                }
+            }
+        }
+        Some(record)
+    }
+
+    fn write_binary(&self, previous_cycle: u64, data: &mut Vec<u8>) {
+        let mut gap = self.cycle - previous_cycle;
+        loop {
+            let byte = (gap & 0x7F) as u8;
+            gap >>= 7;
+            if gap == 0 {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+        data.extend_from_slice(&self.program_counter.to_be_bytes());
+        data.extend_from_slice(&self.opcode.to_be_bytes());
+        data.extend_from_slice(&self.i.to_be_bytes());
+        match self.framebuffer {
+            Some(framebuffer) => {
+                data.push(1);
+                data.extend_from_slice(&framebuffer.to_be_bytes());
+            }
+            None => data.push(0),
+        }
+        data.push(self.registers.len() as u8);
+        for (register, value) in &self.registers {
+            data.extend_from_slice(&[*register, *value]);
+        }
+    }
+}
+
+// Reads a text or a binary trace, whichever the data is
+pub fn read_trace(data: &[u8]) -> Result<Vec<TraceRecord>, TraceError> {
+    if data.starts_with(MAGIC) {
+        return read_binary(data);
+    }
+    let text = String::from_utf8_lossy(data);
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| TraceRecord::from_line(line).ok_or(TraceError::BadLine(index + 1)))
+        .collect()
+}
+
+fn read_binary(data: &[u8]) -> Result<Vec<TraceRecord>, TraceError> {
+    let version = *data.get(MAGIC.len()).ok_or(TraceError::Corrupted)?;
+    if version != TRACE_VERSION {
+        return Err(TraceError::UnsupportedVersion(version));
+    }
+    let mut data = data[MAGIC.len() + 1..].iter().copied();
+    let mut next = || data.next().ok_or(TraceError::Corrupted);
+    let mut records = Vec::new();
+    let mut cycle = 0u64;
+    loop {
+        let mut gap = 0u64;
+        let mut shift = 0;
+        let mut byte = match next() {
+            Ok(byte) => byte,
+            Err(_) => return Ok(records),
+        };
+        loop {
+            if shift > 63 {
+                return Err(TraceError::Corrupted);
+            }
+            gap |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            byte = next()?;
+        }
+        cycle = cycle.checked_add(gap).ok_or(TraceError::Corrupted)?;
+        let mut word = || Ok::<u16, TraceError>(u16::from_be_bytes([next()?, next()?]));
+        let (program_counter, opcode, i) = (word()?, word()?, word()?);
+        let framebuffer = match next()? {
+            0 => None,
+            1 => Some(u32::from_be_bytes([next()?, next()?, next()?, next()?])),
+            _ => return Err(TraceError::Corrupted),
+        };
+        let count = next()?;
+        let mut registers = Vec::new();
+        for _ in 0..count {
+            let (register, value) = (next()?, next()?);
+            if register > 0xF {
+                return Err(TraceError::Corrupted);
+            }
+            registers.push((register, value));
+        }
+        records.push(TraceRecord {
+            cycle,
+            program_counter,
+            opcode,
+            i,
+            registers,
+            framebuffer,
+        });
+    }
+}
+
+// FNV-1a over the resolution and the visible pixels
+pub fn framebuffer_hash(cpu: &Cpu) -> u32 {
+    let (width, height) = cpu.resolution();
+    let mut hash: u32 = 0x811C_9DC5;
+    let mut add = |byte: u8| hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+    add(width as u8);
+    add(height as u8);
+    for row in cpu.read_pixels().iter().take(height) {
+        for pixel in row.iter().take(width) {
+            add(*pixel);
+        }
+    }
+    hash
+}
+
+// Which instructions end up in the trace, all of them by default
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    pub addresses: Option<Range<usize>>,
+    // bit n lets the opcodes starting with the hex digit n through
+    pub classes: Option<u16>,
+    pub cycles: Option<Range<u64>>,
+}
+
+impl TraceFilter {
+    // "200-2FF", both ends included, hex
+    pub fn parse_addresses(text: &str) -> Option<Range<usize>> {
+        let (start, end) = text.split_once('-')?;
+        let start = usize::from_str_radix(start.trim_start_matches("0x"), 16).ok()?;
+        let end = usize::from_str_radix(end.trim_start_matches("0x"), 16).ok()?;
+        Some(start..end + 1)
+    }
+
+    // "8,D,F", the first hex digit of the opcodes to keep
+    pub fn parse_classes(text: &str) -> Option<u16> {
+        text.split(',')
+            .map(|class| {
+                u8::from_str_radix(class.trim(), 16)
+                    .ok()
+                    .filter(|class| *class < 16)
+            })
+            .try_fold(0, |classes, class| Some(classes | 1 << class?))
+    }
+
+    // "1000-2000", both ends included, decimal
+    pub fn parse_cycles(text: &str) -> Option<Range<u64>> {
+        let (start, end) = text.split_once('-')?;
+        Some(start.parse().ok()?..end.parse::<u64>().ok()? + 1)
+    }
+
+    pub fn allows(&self, cycle: u64, address: usize, opcode: u16) -> bool {
+        self.cycles
+            .as_ref()
+            .is_none_or(|cycles| cycles.contains(&cycle))
+            && self
+                .addresses
+                .as_ref()
+                .is_none_or(|addresses| addresses.contains(&address))
+            && self
+                .classes
+                .is_none_or(|classes| classes & 1 << (opcode >> 12) != 0)
+    }
+}
+
+// Writes a record for every instruction the Cpu it is attached to executes
+pub struct Tracer {
+    writer: Box<dyn Write + Send>,
+    format: TraceFormat,
+    filter: TraceFilter,
+    // instructions executed so far, filtered out ones included
+    cycle: u64,
+    // state as of the last record written, the next one lists what changed since
+    last_cycle: u64,
+    registers: [u8; 16],
+    framebuffer: Option<u32>,
+    framebuffer_dirty: bool,
+    // the first write error, writing stops after it
+    error: Option<io::Error>,
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("format", &self.format)
+            .field("filter", &self.filter)
+            .field("cycle", &self.cycle)
+            .finish()
+    }
+}
+
+impl Tracer {
+    pub fn new(writer: Box<dyn Write + Send>, format: TraceFormat, filter: TraceFilter) -> Tracer {
+        let mut tracer = Tracer {
+            writer,
+            format,
+            filter,
+            cycle: 0,
+            last_cycle: 0,
+            registers: [0; 16],
+            framebuffer: None,
+            framebuffer_dirty: true,
+            error: None,
+        };
+        if format == TraceFormat::Binary {
+            let mut header = MAGIC.to_vec();
+            header.push(TRACE_VERSION);
+            tracer.write(&header);
+        }
+        tracer
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.write_all(data) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    // Called by the Cpu after every instruction it executed
+    pub(crate) fn trace(&mut self, cpu: &Cpu, address: usize, opcode: u16) {
+        let cycle = self.cycle;
+        self.cycle += 1;
+        // clears, draws, scrolls and resolution changes all start with 0 or D
+        if opcode >> 12 == 0x0 || opcode >> 12 == 0xD {
+            self.framebuffer_dirty = true;
+        }
+        if !self.filter.allows(cycle, address, opcode) {
+            return;
+        }
+
+        let registers: Vec<(u8, u8)> = (0..16)
+            .filter(|x| cpu.registers()[*x] != self.registers[*x])
+            .map(|x| (x as u8, cpu.registers()[x]))
+            .collect();
+        self.registers = *cpu.registers();
+        let mut framebuffer = None;
+        if self.framebuffer_dirty {
+            self.framebuffer_dirty = false;
+            let hash = framebuffer_hash(cpu);
+            if self.framebuffer != Some(hash) {
+                self.framebuffer = Some(hash);
+                framebuffer = Some(hash);
+            }
+        }
+        let record = TraceRecord {
+            cycle,
+            program_counter: address as u16,
+            opcode,
+            i: cpu.i(),
+            registers,
+            framebuffer,
+        };
+        match self.format {
+            TraceFormat::Text => {
+                let line = record.to_line() + "\n";
+                self.write(line.as_bytes());
+            }
+            TraceFormat::Binary => {
+                let mut data = Vec::new();
+                record.write_binary(self.last_cycle, &mut data);
+                self.write(&data);
+            }
+        }
+        self.last_cycle = cycle;
+    }
+
+    // Flushes the trace, reports the first error writing it hit
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+impl Cpu {
+    // Traces every instruction run_next_instruction executes from now on
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+}
+
+#[cfg(test)]
+#[path = "./trace_test.rs"]
+mod test;
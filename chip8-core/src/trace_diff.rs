@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::trace::TraceRecord;
+
+// Finds the first record where two traces disagree. Records are paired up in order, so both
+// traces need the same filters. Traces only list what changed, so registers, I and the
+// framebuffer hash are tracked from the start and compared in full after every record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    ProgramCounter(u16, u16),
+    Opcode(u16, u16),
+    Register(u8, u8, u8),
+    I(u16, u16),
+    Framebuffer(Option<u32>, Option<u32>),
+    // one trace ended, the lengths of both
+    Length(usize, usize),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::ProgramCounter(left, right) => {
+                write!(f, "PC is {:04X} and {:04X}", left, right)
+            }
+            Difference::Opcode(left, right) => {
+                write!(f, "opcode is {:04X} and {:04X}", left, right)
+            }
+            Difference::Register(x, left, right) => {
+                write!(f, "V{:X} is {:02X} and {:02X}", x, left, right)
+            }
+            Difference::I(left, right) => write!(f, "I is {:04X} and {:04X}", left, right),
+            Difference::Framebuffer(left, right) => {
+                let hash = |hash: &Option<u32>| match hash {
+                    Some(hash) => format!("{:08X}", hash),
+                    None => "unknown".to_string(),
+                };
+                write!(f, "framebuffer is {} and {}", hash(left), hash(right))
+            }
+            Difference::Length(left, right) => {
+                write!(f, "traces end after {} and {} records", left, right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    // index of the first record that differs
+    pub index: usize,
+    pub difference: Difference,
+}
+
+// Registers, I and framebuffer as of a record
+#[derive(Default)]
+struct State {
+    registers: [u8; 16],
+    i: u16,
+    framebuffer: Option<u32>,
+}
+
+impl State {
+    fn apply(&mut self, record: &TraceRecord) {
+        for (register, value) in &record.registers {
+            self.registers[*register as usize] = *value;
+        }
+        self.i = record.i;
+        if record.framebuffer.is_some() {
+            self.framebuffer = record.framebuffer;
+        }
+    }
+}
+
+pub fn first_divergence(left: &[TraceRecord], right: &[TraceRecord]) -> Option<Divergence> {
+    let mut left_state = State::default();
+    let mut right_state = State::default();
+    for (index, (left, right)) in left.iter().zip(right).enumerate() {
+        left_state.apply(left);
+        right_state.apply(right);
+        let difference = if left.program_counter != right.program_counter {
+            Some(Difference::ProgramCounter(
+                left.program_counter,
+                right.program_counter,
+            ))
+        } else if left.opcode != right.opcode {
+            Some(Difference::Opcode(left.opcode, right.opcode))
+        } else if let Some(x) =
+            (0..16).find(|x| left_state.registers[*x] != right_state.registers[*x])
+        {
+            Some(Difference::Register(
+                x as u8,
+                left_state.registers[x],
+                right_state.registers[x],
+            ))
+        } else if left_state.i != right_state.i {
+            Some(Difference::I(left_state.i, right_state.i))
+        } else if left_state.framebuffer != right_state.framebuffer {
+            Some(Difference::Framebuffer(
+                left_state.framebuffer,
+                right_state.framebuffer,
+            ))
+        } else {
+            None
+        };
+        if let Some(difference) = difference {
+            return Some(Divergence { index, difference });
+        }
+    }
+    if left.len() != right.len() {
+        return Some(Divergence {
+            index: left.len().min(right.len()),
+            difference: Difference::Length(left.len(), right.len()),
+        });
+    }
+    None
+}
+
+// The divergence and the records leading up to it from both traces, the diverging one marked with >
+pub fn report(
+    names: (&str, &str),
+    left: &[TraceRecord],
+    right: &[TraceRecord],
+    divergence: &Divergence,
+    context: usize,
+) -> String {
+    let mut report = format!(
+        "first divergence at record {}: {}\n",
+        divergence.index, divergence.difference
+    );
+    for (name, records) in [(names.0, left), (names.1, right)] {
+        report.push_str(&format!("{}:\n", name));
+        let start = divergence.index.saturating_sub(context);
+        let end = (divergence.index + 1).min(records.len());
+        for (index, record) in records.iter().enumerate().take(end).skip(start) {
+            let marker = if index == divergence.index { '>' } else { ' ' };
+            report.push_str(&format!("{} {}\n", marker, record.to_line()));
+        }
+        if divergence.index >= records.len() {
+            report.push_str("> (end of trace)\n");
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+#[path = "./trace_diff_test.rs"]
+mod test;
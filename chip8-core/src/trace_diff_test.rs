@@ -0,0 +1,59 @@
+use super::*;
+
+fn records(text: &str) -> Vec<TraceRecord> {
+    crate::trace::read_trace(text.as_bytes()).unwrap()
+}
+
+const LEFT: &str = "0 0200 6005 LD V0, 0x05 ; I=0000 V0=05 FB=00000001
+1 0202 8014 ADD V0, V1 ; I=0000 VF=00
+2 0204 D011 DRW V0, V1, 1 ; I=0000 FB=00000002
+3 0206 1206 JP 0x206 ; I=0000";
+
+#[test]
+fn first_divergence_test() {
+    let left = records(LEFT);
+    assert_eq!(first_divergence(&left, &left), None);
+
+    // the right trace lists VF again although it did not change, that is no divergence
+    let right = records(&LEFT.replace(
+        "D011 DRW V0, V1, 1 ; I=0000",
+        "D011 DRW V0, V1, 1 ; I=0000 VF=00",
+    ));
+    assert_eq!(first_divergence(&left, &right), None);
+
+    let right = records(&LEFT.replace("VF=00", "VF=01"));
+    assert_eq!(
+        first_divergence(&left, &right),
+        Some(Divergence {
+            index: 1,
+            difference: Difference::Register(0xF, 0, 1)
+        })
+    );
+
+    let right = records(&LEFT.replace("FB=00000002", "FB=00000003"));
+    let divergence = first_divergence(&left, &right).unwrap();
+    assert_eq!(
+        divergence.difference,
+        Difference::Framebuffer(Some(2), Some(3))
+    );
+    let report = report(("ours", "theirs"), &left, &right, &divergence, 1);
+    assert_eq!(
+        report,
+        "first divergence at record 2: framebuffer is 00000002 and 00000003
+ours:
+  1 0202 8014 ADD V0, V1 ; I=0000 VF=00
+> 2 0204 D011 DRW V0, V1, 1 ; I=0000 FB=00000002
+theirs:
+  1 0202 8014 ADD V0, V1 ; I=0000 VF=00
+> 2 0204 D011 DRW V0, V1, 1 ; I=0000 FB=00000003
+"
+    );
+
+    assert_eq!(
+        first_divergence(&left, &left[..2]),
+        Some(Divergence {
+            index: 2,
+            difference: Difference::Length(4, 2)
+        })
+    );
+}
@@ -0,0 +1,86 @@
+use super::*;
+use crate::quirks::Quirks;
+use std::sync::{Arc, Mutex};
+
+// 0x200: V0 := 5, I := 0x300, V0 += 1, clear, jump 0x204
+const ROM: [u8; 10] = [0x60, 0x05, 0xA3, 0x00, 0x70, 0x01, 0x00, 0xE0, 0x12, 0x04];
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn trace(format: TraceFormat, filter: TraceFilter, instructions: usize) -> Vec<u8> {
+    let buffer = Buffer::default();
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    cpu.set_tracer(Some(Tracer::new(Box::new(buffer.clone()), format, filter)));
+    for _ in 0..instructions {
+        cpu.run_next_instruction().unwrap();
+    }
+    cpu.take_tracer().unwrap().finish().unwrap();
+    let data = buffer.0.lock().unwrap().clone();
+    data
+}
+
+#[test]
+fn text_trace_test() {
+    let text = trace(TraceFormat::Text, TraceFilter::default(), 6);
+    let blank = {
+        let mut cpu = Cpu::new(Quirks::default());
+        cpu.load_rom(&ROM);
+        framebuffer_hash(&cpu)
+    };
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        format!(
+            "0 0200 6005 LD V0, 0x05 ; I=0000 V0=05 FB={:08X}\n\
+             1 0202 A300 LD I, 0x300 ; I=0300\n\
+             2 0204 7001 ADD V0, 0x01 ; I=0300 V0=06\n\
+             3 0206 00E0 CLS ; I=0300\n\
+             4 0208 1204 JP 0x204 ; I=0300\n\
+             5 0204 7001 ADD V0, 0x01 ; I=0300 V0=07\n",
+            blank
+        )
+    );
+}
+
+#[test]
+fn filter_test() {
+    let filter = TraceFilter {
+        addresses: TraceFilter::parse_addresses("202-206"),
+        classes: TraceFilter::parse_classes("7,A"),
+        cycles: TraceFilter::parse_cycles("0-4"),
+    };
+    let records = read_trace(&trace(TraceFormat::Text, filter, 6)).unwrap();
+    let cycles: Vec<u64> = records.iter().map(|record| record.cycle).collect();
+    assert_eq!(cycles, vec![1, 2]);
+    // the first record still lists V0, it changed since the last one written
+    assert_eq!(records[1].registers, vec![(0, 6)]);
+    assert_eq!(TraceFilter::parse_classes("8,G"), None);
+}
+
+#[test]
+fn binary_trace_test() {
+    let filter = TraceFilter {
+        cycles: Some(2..200),
+        ..TraceFilter::default()
+    };
+    let binary = trace(TraceFormat::Binary, filter.clone(), 200);
+    let text = trace(TraceFormat::Text, filter, 200);
+    assert!(binary.len() < text.len() / 3);
+    assert_eq!(read_trace(&binary).unwrap(), read_trace(&text).unwrap());
+    assert_eq!(
+        read_trace(&binary[..binary.len() - 1]),
+        Err(TraceError::Corrupted)
+    );
+}
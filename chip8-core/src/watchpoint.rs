@@ -0,0 +1,66 @@
+use std::ops::Range;
+
+use crate::processor::Cpu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    // opcode fetches, including the address word of F000 NNNN
+    Execute,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: Range<usize>,
+    pub access: Access,
+}
+
+// The first watched byte an instruction touched and the address of that instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub address: usize,
+    pub access: Access,
+    pub program_counter: usize,
+}
+
+impl Cpu {
+    // run_next_instruction returns StepOutcome::Watchpoint after an instruction accessed the range
+    pub fn add_watchpoint(&mut self, range: Range<usize>, access: Access) {
+        self.watchpoints.push(Watchpoint { range, access });
+    }
+
+    pub fn remove_watchpoint(&mut self, range: Range<usize>, access: Access) {
+        self.watchpoints
+            .retain(|watchpoint| watchpoint.range != range || watchpoint.access != access);
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    // Called for every memory access the current instruction makes
+    pub(crate) fn watch(&mut self, range: Range<usize>, access: Access) {
+        if self.watch_hit.is_some() {
+            return;
+        }
+        for watchpoint in &self.watchpoints {
+            if watchpoint.access != access {
+                continue;
+            }
+            let start = range.start.max(watchpoint.range.start);
+            if start < range.end.min(watchpoint.range.end) {
+                self.watch_hit = Some(WatchHit {
+                    address: start,
+                    access,
+                    program_counter: self.instruction_address,
+                });
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./watchpoint_test.rs"]
+mod test;
@@ -0,0 +1,81 @@
+use super::*;
+use crate::processor::StepOutcome;
+use crate::quirks::Quirks;
+
+// 0x200: I := 0x300, V0 := 7, save V0, load V0, jump 0x202
+const ROM: [u8; 10] = [0xA3, 0x00, 0x60, 0x07, 0xF0, 0x55, 0xF0, 0x65, 0x12, 0x02];
+
+fn cpu() -> Cpu {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&ROM);
+    cpu
+}
+
+#[test]
+fn write_and_read_watchpoint_test() {
+    let mut cpu = cpu();
+    cpu.add_watchpoint(0x300..0x301, Access::Write);
+    cpu.add_watchpoint(0x2F0..0x301, Access::Read);
+    let outcomes: Vec<StepOutcome> = (0..4)
+        .map(|_| cpu.run_next_instruction().unwrap())
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            StepOutcome::Executed,
+            StepOutcome::Executed,
+            StepOutcome::Watchpoint(WatchHit {
+                address: 0x300,
+                access: Access::Write,
+                program_counter: 0x204,
+            }),
+            StepOutcome::Watchpoint(WatchHit {
+                address: 0x300,
+                access: Access::Read,
+                program_counter: 0x206,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn execute_watchpoint_test() {
+    let mut cpu = cpu();
+    cpu.add_watchpoint(0x209..0x20A, Access::Execute);
+    assert_eq!(
+        cpu.run_frame(10),
+        Ok(StepOutcome::Watchpoint(WatchHit {
+            address: 0x209,
+            access: Access::Execute,
+            program_counter: 0x208,
+        }))
+    );
+    assert_eq!(cpu.program_counter(), 0x202);
+
+    cpu.remove_watchpoint(0x209..0x20A, Access::Execute);
+    assert!(cpu.watchpoints().is_empty());
+    assert_eq!(cpu.run_frame(10), Ok(StepOutcome::Executed));
+}
+
+#[test]
+fn failed_instruction_watchpoint_test() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.load_rom(&[0xFF, 0xFF]);
+    cpu.add_watchpoint(0x200..0x202, Access::Execute);
+    assert!(cpu.run_next_instruction().is_err());
+
+    // the hit of the unknown opcode is gone with it
+    cpu.remove_watchpoint(0x200..0x202, Access::Execute);
+    cpu.load_rom(&[0xA3, 0x00]);
+    assert_eq!(cpu.run_next_instruction(), Ok(StepOutcome::Executed));
+}
+
+#[test]
+fn reset_clears_watchpoints_test() {
+    let mut cpu = cpu();
+    cpu.add_watchpoint(0x300..0x301, Access::Write);
+    cpu.reset();
+    assert!(cpu.watchpoints().is_empty());
+    cpu.load_rom(&ROM);
+    assert_eq!(cpu.run_frame(10), Ok(StepOutcome::Executed));
+}
@@ -0,0 +1,301 @@
+// Runs the test ROMs in tests/roms and the ROMs in assets without a window and compares the
+// screen with the snapshots in tests/golden.
+// The ROMs written for this repository also leave their result in a register that is checked
+// on its own, so a wrong snapshot can not hide a failure.
+// The ROMs of Timendus' chip8-test-suite (https://github.com/Timendus/chip8-test-suite), corax+
+// among them, are not part of the repository. suite_test runs them from
+// tests/roms/chip8-test-suite with the setup listed in SUITE and fails when one is missing, it is
+// ignored unless asked for with `--ignored`. Their snapshots have to match the pass screens shown
+// in the suite's README, not just what this emulator draws.
+// ROMs whose screen depends on the quirks get one snapshot per profile, the others a single one
+// that every profile has to match.
+// After an intended change run `UPDATE_GOLDEN=1 cargo test --test conformance` and review the diff
+use std::env;
+use std::fs::{read, read_dir, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use chip8_core::assembler::assemble;
+use chip8_core::headless::{HeadlessRun, KeyPress};
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::QUIRKS_PROFILES;
+use chip8_core::random::Random;
+use chip8_core::screen_dump::to_ascii;
+
+const INSTRUCTIONS_PER_FRAME: usize = 9;
+// test ROMs stop at a jump to themselves long before this
+const TEST_ROM_FRAMES: usize = 600;
+// games never stop, this is far enough to get past their title screens
+const GAME_FRAMES: usize = 300;
+
+// The suite's ROMs read which platform or test to run from 0x1FF instead of showing a menu
+const SUITE_SELECTION: usize = 0x1FF;
+
+// Keys held while the ROMs in tests/roms run, see KeyPress::parse
+const ROM_KEYS: [(&str, &[&str]); 1] = [("keypad", &["60-70:5", "200-500:7"])];
+
+struct SuiteRom {
+    file: &'static str,
+    per_profile: bool,
+    // written to SUITE_SELECTION, by quirks profile
+    selection: fn(&str) -> Option<u8>,
+    keys: &'static [&'static str],
+    until_loop: bool,
+}
+
+const SUITE: [SuiteRom; 6] = [
+    SuiteRom {
+        file: "1-chip8-logo.ch8",
+        per_profile: false,
+        selection: |_| None,
+        keys: &[],
+        until_loop: true,
+    },
+    SuiteRom {
+        file: "2-ibm-logo.ch8",
+        per_profile: false,
+        selection: |_| None,
+        keys: &[],
+        until_loop: true,
+    },
+    SuiteRom {
+        file: "3-corax+.ch8",
+        per_profile: false,
+        selection: |_| None,
+        keys: &[],
+        until_loop: true,
+    },
+    SuiteRom {
+        file: "4-flags.ch8",
+        per_profile: false,
+        selection: |_| None,
+        keys: &[],
+        until_loop: true,
+    },
+    // 1 CHIP-8, 2 SUPER-CHIP, 3 XO-CHIP
+    SuiteRom {
+        file: "5-quirks.ch8",
+        per_profile: true,
+        selection: |profile| match profile {
+            "SUPER-CHIP" => Some(2),
+            "XO-CHIP" => Some(3),
+            _ => Some(1),
+        },
+        keys: &[],
+        until_loop: false,
+    },
+    // 3 is the FX0A test, it passes once a key has been pressed and released
+    SuiteRom {
+        file: "6-keypad.ch8",
+        per_profile: false,
+        selection: |_| Some(3),
+        keys: &["60-70:5"],
+        until_loop: false,
+    },
+];
+
+struct Rom {
+    name: String,
+    data: Vec<u8>,
+    until_loop: bool,
+    per_profile: bool,
+    selection: fn(&str) -> Option<u8>,
+    keys: Vec<KeyPress>,
+}
+
+impl Rom {
+    fn new(name: String, data: Vec<u8>, until_loop: bool) -> Rom {
+        Rom {
+            name,
+            data,
+            until_loop,
+            per_profile: false,
+            selection: |_| None,
+            keys: Vec::new(),
+        }
+    }
+}
+
+fn roms(directory: &Path, until_loop: bool) -> Vec<Rom> {
+    let mut paths: Vec<PathBuf> = read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    let mut roms = Vec::new();
+    for path in paths {
+        let data = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ch8") => read(&path).unwrap(),
+            Some("asm") => match assemble(&read_to_string(&path).unwrap()) {
+                Ok(data) => data,
+                Err(error) => panic!("{}: {}", path.display(), error),
+            },
+            _ => continue,
+        };
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let mut rom = Rom::new(name, data, until_loop);
+        rom.per_profile = rom.name == "quirks";
+        if let Some((_, keys)) = ROM_KEYS.iter().find(|(name, _)| *name == rom.name) {
+            rom.keys = key_presses(keys);
+        }
+        roms.push(rom);
+    }
+    roms
+}
+
+fn key_presses(keys: &[&str]) -> Vec<KeyPress> {
+    keys.iter()
+        .map(|keys| KeyPress::parse(keys).unwrap())
+        .collect()
+}
+
+// The suite is not part of the repository, every ROM of it has to be copied in
+fn suite_roms(directory: &Path) -> Result<Vec<Rom>, Vec<String>> {
+    let mut roms = Vec::new();
+    let mut missing = Vec::new();
+    for suite_rom in &SUITE {
+        let path = directory.join(suite_rom.file);
+        let data = match read(&path) {
+            Ok(data) => data,
+            Err(error) => {
+                missing.push(format!("{}: {}", path.display(), error));
+                continue;
+            }
+        };
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let mut rom = Rom::new(name, data, suite_rom.until_loop);
+        rom.per_profile = suite_rom.per_profile;
+        rom.selection = suite_rom.selection;
+        rom.keys = key_presses(suite_rom.keys);
+        roms.push(rom);
+    }
+    if missing.is_empty() {
+        Ok(roms)
+    } else {
+        Err(missing)
+    }
+}
+
+// "COSMAC VIP" is stored as cosmac-vip
+fn profile_file_name(profile: &str) -> String {
+    profile.to_ascii_lowercase().replace(' ', "-")
+}
+
+fn run(rom: &Rom, profile: usize) -> Result<Cpu, String> {
+    let (profile_name, quirks) = QUIRKS_PROFILES[profile];
+    let mut cpu = Cpu::new(quirks);
+    cpu.set_random(Random::new(0));
+    cpu.load_rom(&rom.data);
+    if let Some(selection) = (rom.selection)(profile_name) {
+        cpu.write_memory(SUITE_SELECTION, &[selection]);
+    }
+    let frames = if rom.until_loop {
+        TEST_ROM_FRAMES
+    } else {
+        GAME_FRAMES
+    };
+    let mut run = HeadlessRun::new(frames, INSTRUCTIONS_PER_FRAME);
+    run.until_loop = rom.until_loop;
+    run.keys = rom.keys.clone();
+    run.run(&mut cpu).map_err(|error| error.to_string())?;
+    Ok(cpu)
+}
+
+// What the ROMs written for this repository report about themselves
+fn self_check(rom: &Rom, profile: usize, cpu: &Cpu) -> Option<String> {
+    let (profile_name, quirks) = QUIRKS_PROFILES[profile];
+    let registers = cpu.registers();
+    match rom.name.as_str() {
+        "flags" | "opcodes" | "keypad" if registers[0xD] != 0 => Some(format!(
+            "{} under {}: {} checks failed",
+            rom.name, profile_name, registers[0xD]
+        )),
+        "quirks" if registers[0xE] != quirks.to_bits() => Some(format!(
+            "quirks under {}: found quirks {:06b}, the profile has {:06b}",
+            profile_name,
+            registers[0xE],
+            quirks.to_bits()
+        )),
+        _ => None,
+    }
+}
+
+// Runs every ROM under every quirks profile, the differences to the snapshots are returned
+fn compare(roms: &[Rom]) -> Vec<String> {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut failures = Vec::new();
+    for rom in roms {
+        for (profile, (profile_name, _)) in QUIRKS_PROFILES.iter().enumerate() {
+            let screen = match run(rom, profile) {
+                Ok(cpu) => {
+                    failures.extend(self_check(rom, profile, &cpu));
+                    to_ascii(&cpu)
+                }
+                Err(error) => format!("error: {}\n", error),
+            };
+            let path = if rom.per_profile {
+                golden.join(format!(
+                    "{}.{}.txt",
+                    rom.name,
+                    profile_file_name(profile_name)
+                ))
+            } else {
+                golden.join(format!("{}.txt", rom.name))
+            };
+            if update {
+                // a shared snapshot is written by the first profile and checked against the rest
+                if rom.per_profile || profile == 0 {
+                    write(&path, &screen).unwrap();
+                    continue;
+                }
+            }
+            match read_to_string(&path) {
+                Ok(expected) if expected == screen => {}
+                Ok(expected) => failures.push(format!(
+                    "{} under {} differs from {}\nexpected:\n{}got:\n{}",
+                    rom.name,
+                    profile_name,
+                    path.display(),
+                    expected,
+                    screen
+                )),
+                Err(_) => failures.push(format!(
+                    "{} under {} has no snapshot at {}, run with UPDATE_GOLDEN=1",
+                    rom.name,
+                    profile_name,
+                    path.display()
+                )),
+            }
+        }
+    }
+    failures
+}
+
+#[test]
+fn conformance_test() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut all_roms = roms(&root.join("tests/roms"), true);
+    all_roms.extend(roms(&root.join("../assets"), false));
+    assert!(!all_roms.is_empty());
+
+    let failures = compare(&all_roms);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore = "needs Timendus' chip8-test-suite in tests/roms/chip8-test-suite"]
+fn suite_test() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms/chip8-test-suite");
+    let suite = match suite_roms(&directory) {
+        Ok(suite) => suite,
+        Err(missing) => panic!(
+            "copy the suite's ROMs in, see {}:\n{}",
+            directory.join("README.md").display(),
+            missing.join("\n")
+        ),
+    };
+
+    let failures = compare(&suite);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,65 @@
+// Runs ROMs made of random bytes, the same way the fuzz targets in fuzz/ do but with fixed
+// seeds so every `cargo test` covers them. Errors are fine, panics are not
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::QUIRKS_PROFILES;
+use chip8_core::random::Random;
+use chip8_core::RAM;
+
+const ROMS: u64 = 200;
+const FRAMES: usize = 200;
+const INSTRUCTIONS_PER_FRAME: usize = 50;
+
+fn run(rom: &[u8], profile: usize, keys: u16) {
+    let mut cpu = Cpu::new(QUIRKS_PROFILES[profile % QUIRKS_PROFILES.len()].1);
+    cpu.load_rom(rom);
+    let mut key_map = [false; 16];
+    for (key, pressed) in key_map.iter_mut().enumerate() {
+        *pressed = keys & 1 << key != 0;
+    }
+    cpu.load_key_map(&key_map);
+    for _ in 0..FRAMES {
+        if cpu.run_frame(INSTRUCTIONS_PER_FRAME).is_err() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn random_roms_test() {
+    let mut random = Random::new(0xC8);
+    for seed in 0..ROMS {
+        let len = random.next_u64() as usize % 4096;
+        let rom: Vec<u8> = (0..len).map(|_| random.next_byte()).collect();
+        run(&rom, seed as usize, random.next_u64() as u16);
+    }
+}
+
+// Random bytes rarely reach the edges of memory, these start right at them
+#[test]
+fn memory_edges_test() {
+    // I at 0xFFFF or 0x0FFF, then every opcode that reads or writes at I
+    let at_i = [
+        0xD0, 0x1F, 0xD0, 0x10, 0xF2, 0x33, 0xFF, 0x55, 0xFF, 0x65, 0x50, 0xF2, 0x5F, 0x03, 0xF0,
+        0x02, 0xF0, 0x1E,
+    ];
+    for i in [
+        [0xF0, 0x00, 0xFF, 0xFF],
+        [0xF0, 0x00, 0x0F, 0xFF],
+        [0xAF, 0xFF, 0, 0],
+    ] {
+        for opcode in at_i.chunks(2) {
+            let mut rom = i.to_vec();
+            rom.extend_from_slice(opcode);
+            for profile in 0..QUIRKS_PROFILES.len() {
+                run(&rom, profile, 0);
+            }
+        }
+    }
+
+    // a ROM that does not fit into memory, jumps and calls to the last address
+    let rom: Vec<u8> = [0x60, 0x00].iter().cycle().take(RAM).copied().collect();
+    run(&rom, 0, 0);
+    run(&[0x1F, 0xFF], 0, 0);
+    run(&[0x2F, 0xFF], 0, 0);
+    run(&[0xBF, 0xFF], 2, 0);
+}
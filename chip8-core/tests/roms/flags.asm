@@ -0,0 +1,159 @@
+; Checks the results and VF of the arithmetic opcodes, a tick for every check that passes
+; and a cross for every one that fails. None of the checks depend on quirks.
+; V0, V1 operands, V2 result, V3 expected result, V4 VF, V5 expected VF, V6/V7 cursor,
+; VD the number of failed checks, which tests/conformance.rs expects to be 0
+
+main:
+    CLS
+    LD V6, 0
+    LD V7, 0
+    LD VD, 0
+
+    ; 8XY4 without and with carry
+    LD V0, 0x10
+    LD V1, 0x20
+    ADD V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x30
+    LD V5, 0
+    CALL check
+    LD V0, 0xFF
+    LD V1, 0x02
+    ADD V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x01
+    LD V5, 1
+    CALL check
+
+    ; 8XY5 without borrow, with borrow and with equal operands, which is no borrow
+    LD V0, 0x30
+    LD V1, 0x10
+    SUB V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x20
+    LD V5, 1
+    CALL check
+    LD V0, 0x10
+    LD V1, 0x30
+    SUB V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0xE0
+    LD V5, 0
+    CALL check
+    LD V0, 0x20
+    LD V1, 0x20
+    SUB V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0
+    LD V5, 1
+    CALL check
+
+    ; 8XY7 the same way
+    LD V0, 0x10
+    LD V1, 0x30
+    SUBN V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x20
+    LD V5, 1
+    CALL check
+    LD V0, 0x30
+    LD V1, 0x10
+    SUBN V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0xE0
+    LD V5, 0
+    CALL check
+    LD V0, 0x20
+    LD V1, 0x20
+    SUBN V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0
+    LD V5, 1
+    CALL check
+
+    ; 8XY6 and 8XYE, VX and VY hold the same value so the shift quirk does not matter
+    LD V0, 0x05
+    LD V1, 0x05
+    SHR V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x02
+    LD V5, 1
+    CALL check
+    LD V0, 0x81
+    LD V1, 0x81
+    SHL V0, V1
+    LD V4, VF
+    LD V2, V0
+    LD V3, 0x02
+    LD V5, 1
+    CALL check
+
+    ; with VF as VX the flag is written last and wins over the result
+    LD VF, 0xFF
+    LD V1, 0x02
+    ADD VF, V1
+    LD V2, VF
+    LD V4, VF
+    LD V3, 1
+    LD V5, 1
+    CALL check
+    LD VF, 0x10
+    LD V1, 0x30
+    SUB VF, V1
+    LD V2, VF
+    LD V4, VF
+    LD V3, 0
+    LD V5, 0
+    CALL check
+    LD VF, 0x30
+    LD V1, 0x10
+    SUBN VF, V1
+    LD V2, VF
+    LD V4, VF
+    LD V3, 0
+    LD V5, 0
+    CALL check
+    LD VF, 0x05
+    SHR VF
+    LD V2, VF
+    LD V4, VF
+    LD V3, 1
+    LD V5, 1
+    CALL check
+
+end:
+    JP end
+
+; Draws a tick when V2 = V3 and V4 = V5, a cross otherwise, then moves the cursor
+check:
+    LD I, tick
+    SE V2, V3
+    JP failed
+    SE V4, V5
+    JP failed
+    JP draw
+failed:
+    LD I, cross
+    ADD VD, 1
+draw:
+    DRW V6, V7, 4
+    ADD V6, 5
+    SE V6, 60
+    RET
+    LD V6, 0
+    ADD V7, 5
+    RET
+
+tick:
+    db 0b00010000, 0b00100000, 0b10100000, 0b01000000
+cross:
+    db 0b10100000, 0b01000000, 0b10100000, 0b00000000
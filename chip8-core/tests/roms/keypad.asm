@@ -0,0 +1,72 @@
+; Checks EX9E, EXA1 and FX0A with the keys tests/conformance.rs holds down: 5 from frame 60
+; to 70, 7 from frame 200 on. A tick for every check that passes and a cross for every one
+; that fails, none of them depend on quirks.
+; V2 result, V3 expected result, V6/V7 cursor,
+; VD the number of failed checks, which tests/conformance.rs expects to be 0
+
+main:
+    CLS
+    LD V6, 0
+    LD V7, 0
+    LD VD, 0
+
+    ; before any key is held EXA1 skips and EX9E does not
+    LD V0, 5
+    LD V2, 0
+    SKNP V0
+    LD V2, 1
+    LD V3, 0
+    CALL check
+    LD V2, 0
+    SKP V0
+    LD V2, 1
+    LD V3, 1
+    CALL check
+
+    ; FX0A waits for 5 to be pressed and released
+    LD V1, K
+    LD V2, V1
+    LD V3, 5
+    CALL check
+
+    ; once 7 is held EX9E skips and EXA1 does not
+    LD V0, 7
+wait:
+    SKP V0
+    JP wait
+    LD V2, 0
+    SKP V0
+    LD V2, 1
+    LD V3, 0
+    CALL check
+    LD V2, 0
+    SKNP V0
+    LD V2, 1
+    LD V3, 1
+    CALL check
+
+end:
+    JP end
+
+; Draws a tick when V2 = V3, a cross otherwise, then moves the cursor
+check:
+    LD I, tick
+    SE V2, V3
+    JP failed
+    JP draw
+failed:
+    LD I, cross
+    ADD VD, 1
+draw:
+    DRW V6, V7, 4
+    ADD V6, 5
+    SE V6, 60
+    RET
+    LD V6, 0
+    ADD V7, 5
+    RET
+
+tick:
+    db 0b00010000, 0b00100000, 0b10100000, 0b01000000
+cross:
+    db 0b10100000, 0b01000000, 0b10100000, 0b00000000
@@ -0,0 +1,184 @@
+; Checks the CHIP-8 opcodes that flags.asm does not, in the spirit of corax+: a tick for every
+; check that passes and a cross for every one that fails. None of the checks depend on quirks,
+; VF is not checked after the logic opcodes and I is set again before every FX55/FX65.
+; V2 result, V3 expected result, V4 VF kept from before a check, V6/V7 cursor,
+; VD the number of failed checks, which tests/conformance.rs expects to be 0
+
+main:
+    CLS
+    LD V6, 0
+    LD V7, 0
+    LD VD, 0
+
+    ; 3XNN and 4XNN skip, the LD V2, 1 after them must not run
+    LD V0, 0x42
+    LD V2, 0
+    SE V0, 0x42
+    LD V2, 1
+    LD V3, 0
+    CALL check
+    LD V2, 0
+    SNE V0, 0x43
+    LD V2, 1
+    CALL check
+
+    ; 5XY0 and 9XY0
+    LD V1, 0x42
+    LD V2, 0
+    SE V0, V1
+    LD V2, 1
+    CALL check
+    LD V1, 0x43
+    LD V2, 0
+    SNE V0, V1
+    LD V2, 1
+    CALL check
+
+    ; 7XNN wraps around and leaves VF alone
+    LD VF, 0x55
+    LD V2, 0xF0
+    ADD V2, 0x20
+    LD V4, VF
+    LD V3, 0x10
+    CALL check
+    LD V2, V4
+    LD V3, 0x55
+    CALL check
+
+    ; 8XY0 to 8XY3
+    LD V0, 0x3C
+    LD V2, V0
+    LD V3, 0x3C
+    CALL check
+    LD V1, 0x0F
+    LD V2, V0
+    OR V2, V1
+    LD V3, 0x3F
+    CALL check
+    LD V2, V0
+    AND V2, V1
+    LD V3, 0x0C
+    CALL check
+    LD V2, V0
+    XOR V2, V1
+    LD V3, 0x33
+    CALL check
+
+    ; 2NNN and 00EE
+    LD V2, 0
+    CALL subroutine
+    LD V3, 0x99
+    CALL check
+
+    ; BNNN with V0 and V2 both 0, so both ways of reading it land on jumped
+    LD V0, 0
+    LD V2, 0
+    JP V0, jumped
+    LD V2, 1
+jumped:
+    LD V3, 0
+    CALL check
+
+    ; CXNN with a mask of 0
+    RND V2, 0
+    LD V3, 0
+    CALL check
+
+    ; ANNN, FX1E and FX65
+    LD I, data
+    LD V0, 2
+    ADD I, V0
+    LD V0, [I]
+    LD V2, V0
+    LD V3, 0xCC
+    CALL check
+
+    ; FX33 and FX55, read back with FX65
+    LD V0, 137
+    LD I, scratch
+    LD B, V0
+    LD I, scratch
+    LD V2, [I]
+    LD V3, 7
+    CALL check
+    LD V2, V1
+    LD V3, 3
+    CALL check
+    LD V2, V0
+    LD V3, 1
+    CALL check
+    LD V0, 0x12
+    LD V1, 0x34
+    LD I, scratch
+    LD [I], V1
+    LD I, scratch
+    LD V1, [I]
+    LD V2, V1
+    LD V3, 0x34
+    CALL check
+
+    ; FX29 points I at the font, the top row of 1 is 0x20
+    LD V0, 1
+    LD F, V0
+    LD V0, [I]
+    LD V2, V0
+    LD V3, 0x20
+    CALL check
+
+    ; FX15 and FX07, the delay timer counts down to 0
+    LD V0, 3
+    LD DT, V0
+wait:
+    LD V2, DT
+    SE V2, 0
+    JP wait
+    LD V3, 0
+    CALL check
+
+    ; DXYN sets VF when it erases a pixel, the second draw erases the first one
+    LD I, tick
+    LD V0, 0
+    LD V1, 27
+    DRW V0, V1, 4
+    LD V2, VF
+    LD V3, 0
+    CALL check
+    LD I, tick
+    DRW V0, V1, 4
+    LD V2, VF
+    LD V3, 1
+    CALL check
+
+end:
+    JP end
+
+subroutine:
+    LD V2, 0x99
+    RET
+
+; Draws a tick when V2 = V3, a cross otherwise, then moves the cursor
+check:
+    LD I, tick
+    SE V2, V3
+    JP failed
+    JP draw
+failed:
+    LD I, cross
+    ADD VD, 1
+draw:
+    DRW V6, V7, 4
+    ADD V6, 5
+    SE V6, 60
+    RET
+    LD V6, 0
+    ADD V7, 5
+    RET
+
+tick:
+    db 0b00010000, 0b00100000, 0b10100000, 0b01000000
+cross:
+    db 0b10100000, 0b01000000, 0b10100000, 0b00000000
+data:
+    db 0xAA, 0xBB, 0xCC
+scratch:
+    db 0, 0, 0
@@ -0,0 +1,109 @@
+; Shows a digit for every quirk, 1 when the Cpu behaves the quirky way:
+; shift uses VY, load/store increments I, jump uses VX, clip sprites, logic resets VF,
+; SUPER-CHIP low resolution
+; VA digit, VB/VC cursor, VE the quirks found in the bit order of Quirks::to_bits, VD the next bit
+
+main:
+    CLS
+    LD VB, 0
+    LD VC, 0
+    LD VD, 1
+    LD VE, 0
+
+    ; 8XY6 shifts VY into VX
+    LD V0, 0x01
+    LD V1, 0x08
+    SHR V0, V1
+    LD VA, 0
+    SNE V0, 0x04
+    LD VA, 1
+    CALL show
+
+    ; FX55 leaves I past the stored register, FX65 then reads the next byte
+    LD I, scratch
+    LD V0, 0xAA
+    LD [I], V0
+    LD V0, [I]
+    LD VA, 0
+    SNE V0, 0xBB
+    LD VA, 1
+    CALL show
+
+    ; BNNN jumps to NNN + V2 instead of NNN + V0, the target is at 0x2XX
+    LD V0, 0
+    LD V2, 2
+    LD VA, 0
+    JP V0, jump_target
+jump_back:
+    CALL show
+
+    ; DXYN wraps the sprite at the right edge around to column 0 unless it clips,
+    ; drawn below the digits and erased again
+    LD V0, 62
+    LD V1, 20
+    LD V2, 0
+    LD I, wide
+    DRW V0, V1, 1
+    LD I, dot
+    DRW V2, V1, 1
+    LD VA, 1
+    SNE VF, 1
+    LD VA, 0
+    DRW V2, V1, 1
+    LD I, wide
+    DRW V0, V1, 1
+    CALL show
+
+    ; 8XY1 sets VF to 0
+    LD VF, 5
+    LD V0, 1
+    LD V1, 2
+    OR V0, V1
+    LD VA, 0
+    SNE VF, 0
+    LD VA, 1
+    CALL show
+
+    ; DXY0 draws 8x16 instead of 16x16, big has its first 8 rows in the first 16 bytes so that
+    ; only the 16x16 sprite covers the dot below it, drawn below the digits and erased again
+    LD V0, 40
+    LD V1, 16
+    LD V2, 24
+    LD I, big
+    DRW V0, V1, 0
+    LD I, dot
+    DRW V0, V2, 1
+    LD VA, 1
+    SNE VF, 1
+    LD VA, 0
+    DRW V0, V2, 1
+    LD I, big
+    DRW V0, V1, 0
+    CALL show
+
+end:
+    JP end
+
+jump_target:
+    JP jump_back
+    LD VA, 1
+    JP jump_back
+
+show:
+    LD F, VA
+    DRW VB, VC, 5
+    ADD VB, 5
+    SNE VA, 1
+    OR VE, VD
+    ADD VD, VD
+    RET
+
+scratch:
+    db 0x00, 0xBB
+wide:
+    db 0xFF
+dot:
+    db 0x80
+big:
+    db 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00
+    db 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
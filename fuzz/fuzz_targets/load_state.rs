@@ -0,0 +1,30 @@
+#![no_main]
+
+// Loads a save state with arbitrary bytes in it and runs whatever got loaded. A real state
+// is far longer than fuzz inputs, so the input overwrites the end of a valid one: registers,
+// stack, pixels and flags, everything after memory. run_rom covers memory
+use libfuzzer_sys::fuzz_target;
+
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::Quirks;
+use chip8_core::screen_dump::to_ascii;
+
+const FRAMES: usize = 10;
+const INSTRUCTIONS_PER_FRAME: usize = 100;
+
+fuzz_target!(|data: &[u8]| {
+    let mut cpu = Cpu::new(Quirks::default());
+    let mut state = cpu.save_state(&[]);
+    let start = state.len() - data.len().min(state.len() - 1);
+    let end = state.len();
+    state[start..end].copy_from_slice(&data[..end - start]);
+    if cpu.load_state(&[], &state).is_err() {
+        return;
+    }
+    for _ in 0..FRAMES {
+        if cpu.run_frame(INSTRUCTIONS_PER_FRAME).is_err() {
+            break;
+        }
+    }
+    to_ascii(&cpu);
+});
@@ -0,0 +1,35 @@
+#![no_main]
+
+// Runs arbitrary bytes as a ROM. The first byte picks the quirks profile, the next two the
+// keys held down. Errors are expected, any panic is a bug
+use libfuzzer_sys::fuzz_target;
+
+use chip8_core::processor::Cpu;
+use chip8_core::quirks::QUIRKS_PROFILES;
+use chip8_core::screen_dump::to_ascii;
+
+const FRAMES: usize = 100;
+const INSTRUCTIONS_PER_FRAME: usize = 100;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 3 {
+        return;
+    }
+    let (header, rom) = data.split_at(3);
+    let quirks = QUIRKS_PROFILES[header[0] as usize % QUIRKS_PROFILES.len()].1;
+    let keys = u16::from_be_bytes([header[1], header[2]]);
+    let mut key_map = [false; 16];
+    for (key, pressed) in key_map.iter_mut().enumerate() {
+        *pressed = keys & 1 << key != 0;
+    }
+
+    let mut cpu = Cpu::new(quirks);
+    cpu.load_rom(rom);
+    cpu.load_key_map(&key_map);
+    for _ in 0..FRAMES {
+        if cpu.run_frame(INSTRUCTIONS_PER_FRAME).is_err() {
+            break;
+        }
+    }
+    to_ascii(&cpu);
+});
@@ -2,22 +2,80 @@ use rodio::source::{SineWave, Source};
 use rodio::OutputStreamHandle;
 use std::time::Duration;
 
-use super::TIMER_RATE;
-use crate::processor::Cpu;
+use chip8_core::processor::Cpu;
+use chip8_core::TIMER_RATE;
+
+const SAMPLE_RATE: u32 = 44100;
 
 pub struct Audio {
-    frequency: f32,
+    pub frequency: f32,
+    // 0.0 to 1.0
+    pub volume: f32,
+    muted: bool,
     duration_ms: u64,
     stream_handle: OutputStreamHandle,
     playing: bool,
 }
 
+// XO-CHIP audio: a 128 bit pattern played one bit per sample
+// at 4000 * 2^((pitch - 64) / 48) bits per second, looping
+struct PatternWave {
+    pattern: [u8; 16],
+    bit_rate: f32,
+    position: f32,
+}
+
+impl PatternWave {
+    fn new(pattern: [u8; 16], pitch: u8) -> PatternWave {
+        PatternWave {
+            pattern,
+            bit_rate: 4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0),
+            position: 0.0,
+        }
+    }
+}
+
+impl Iterator for PatternWave {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let bit = self.position as usize % 128;
+        let byte = self.pattern[bit / 8];
+        self.position = (self.position + self.bit_rate / SAMPLE_RATE as f32) % 128.0;
+        if (byte >> (7 - bit % 8)) & 1 == 1 {
+            Some(1.0)
+        } else {
+            Some(-1.0)
+        }
+    }
+}
+
+impl Source for PatternWave {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
 impl Audio {
-    pub fn new(frequency: f32, stream_handle: OutputStreamHandle) -> Audio {
+    pub fn new(frequency: f32, muted: bool, stream_handle: OutputStreamHandle) -> Audio {
         Audio {
-            frequency: frequency,
+            frequency,
+            volume: 0.2,
+            muted,
             duration_ms: 0,
-            stream_handle: stream_handle,
+            stream_handle,
             playing: false,
         }
     }
@@ -25,20 +83,33 @@ impl Audio {
     fn calc_audio_duration(&self, cpu: &Cpu) -> u64 {
         let sound_timer = cpu.check_sound_timer() as u64;
         let micros_left = sound_timer * TIMER_RATE;
-        let millis_left = micros_left / 1000;
-        millis_left
+        micros_left / 1000
     }
 
     pub fn play(&mut self, cpu: &Cpu) {
+        if self.muted {
+            return;
+        }
         if !self.playing && cpu.check_sound_timer() > 0 {
             self.duration_ms = self.calc_audio_duration(cpu);
             self.playing = true;
-            let source = SineWave::new(self.frequency)
-                .take_duration(Duration::from_millis(self.duration_ms))
-                .amplify(0.20);
-            self.stream_handle
-                .play_raw(source.convert_samples())
-                .unwrap();
+            let duration = Duration::from_millis(self.duration_ms);
+            match cpu.audio_pattern() {
+                Some(pattern) => {
+                    let source = PatternWave::new(*pattern, cpu.pitch())
+                        .take_duration(duration)
+                        .amplify(self.volume);
+                    self.stream_handle.play_raw(source).unwrap();
+                }
+                None => {
+                    let source = SineWave::new(self.frequency)
+                        .take_duration(duration)
+                        .amplify(self.volume);
+                    self.stream_handle
+                        .play_raw(source.convert_samples())
+                        .unwrap();
+                }
+            }
         }
         if self.playing && cpu.check_sound_timer() == 0 {
             self.playing = false;
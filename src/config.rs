@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::keypad::parse_key;
+use crate::options::{parse_palette, quirks_index, Options};
+
+// The config file, chip8/config.toml in the user config directory ($XDG_CONFIG_HOME on Linux).
+// Global settings at the top, then a [roms.<sha1>] table for every ROM that needs its own:
+//
+//   rom_directories = ["assets/", "/home/me/roms"]
+//   instructions_per_frame = 9
+//   quirks = "CHIP-8"
+//   palette = "000000,FFFFFF"
+//   volume = 0.2
+//   [keys]
+//   5 = "Up"
+//
+//   [roms.0123456789abcdef0123456789abcdef01234567]
+//   name = "pong"
+//   instructions_per_frame = 20
+//   quirks = "SUPER-CHIP"
+//
+// name is only there for whoever reads the file, other keys nobody reads are reported by
+// unknown_keys so that a typo does not go unnoticed
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    rom_directories: Option<Vec<String>>,
+    scale: Option<u32>,
+    fullscreen: Option<bool>,
+    mute: Option<bool>,
+    font: Option<String>,
+    rewind_seconds: Option<usize>,
+    #[serde(flatten)]
+    settings: Settings,
+    #[serde(default)]
+    roms: BTreeMap<String, Settings>,
+}
+
+// What can be set for everything and for a single ROM
+#[derive(Debug, Default, Deserialize)]
+struct Settings {
+    instructions_per_frame: Option<usize>,
+    quirks: Option<String>,
+    palette: Option<String>,
+    buzzer_frequency: Option<f32>,
+    volume: Option<f32>,
+    // CHIP-8 key in hex to keyboard key, only the keys listed move
+    keys: Option<BTreeMap<String, String>>,
+    #[allow(dead_code)]
+    name: Option<String>,
+    // whatever is left, serde's deny_unknown_fields does not work together with flatten
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+impl Settings {
+    fn apply(&self, options: &mut Options) -> Result<(), String> {
+        if let Some(instructions_per_frame) = self.instructions_per_frame {
+            if instructions_per_frame == 0 {
+                return Err("instructions_per_frame needs to be at least 1".to_string());
+            }
+            options.instructions_per_frame = instructions_per_frame;
+        }
+        if let Some(name) = &self.quirks {
+            options.quirks =
+                quirks_index(name).ok_or_else(|| format!("unknown quirks profile `{}`", name))?;
+        }
+        if let Some(colors) = &self.palette {
+            options.palette =
+                parse_palette(colors).ok_or_else(|| format!("bad palette `{}`", colors))?;
+        }
+        if let Some(frequency) = self.buzzer_frequency {
+            options.buzzer_frequency = frequency;
+        }
+        if let Some(volume) = self.volume {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err("volume goes from 0.0 to 1.0".to_string());
+            }
+            options.volume = volume;
+        }
+        for (chip8_key, key) in self.keys.iter().flatten() {
+            let chip8_key = u8::from_str_radix(chip8_key, 16)
+                .ok()
+                .filter(|chip8_key| *chip8_key < 16)
+                .ok_or_else(|| format!("`{}` is not a CHIP-8 key, 0 to F", chip8_key))?;
+            options.keys[chip8_key as usize] =
+                parse_key(key).ok_or_else(|| format!("unknown key `{}`", key))?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config: Config = toml::from_str(text).map_err(|error| error.to_string())?;
+        // mistakes in a ROM section would otherwise only show up once that ROM is picked
+        config.settings.apply(&mut Options::new())?;
+        for (sha1, settings) in &config.roms {
+            if sha1.len() != 40 || !sha1.chars().all(|digit| digit.is_ascii_hexdigit()) {
+                return Err(format!("`{}` is not the SHA-1 of a ROM", sha1));
+            }
+            settings
+                .apply(&mut Options::new())
+                .map_err(|error| format!("roms.{}: {}", sha1, error))?;
+        }
+        config.roms = config
+            .roms
+            .into_iter()
+            .map(|(sha1, settings)| (sha1.to_ascii_lowercase(), settings))
+            .collect();
+        Ok(config)
+    }
+
+    // Keys that were read but mean nothing, as `roms.<sha1>.<key>` for the ROM sections
+    pub fn unknown_keys(&self) -> Vec<String> {
+        let global = self.settings.unknown.keys().cloned();
+        let roms = self.roms.iter().flat_map(|(sha1, settings)| {
+            settings
+                .unknown
+                .keys()
+                .map(move |key| format!("roms.{}.{}", sha1, key))
+        });
+        global.chain(roms).collect()
+    }
+
+    // A missing file is an empty config, unless it was asked for with --config
+    pub fn load(path: &Path, required: bool) -> Result<Config, String> {
+        match read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(error) if error.kind() == ErrorKind::NotFound && !required => Ok(Config::default()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    // Global settings, the command line goes on top of these
+    pub fn apply(&self, options: &mut Options) -> Result<(), String> {
+        if let Some(directories) = &self.rom_directories {
+            options.rom_paths = directories.clone();
+        }
+        if let Some(scale) = self.scale {
+            if scale == 0 {
+                return Err("scale needs to be at least 1".to_string());
+            }
+            options.scale = scale;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            options.fullscreen = fullscreen;
+        }
+        if let Some(mute) = self.mute {
+            options.mute = mute;
+        }
+        if let Some(font) = &self.font {
+            options.font_path = font.clone();
+        }
+        if let Some(rewind_seconds) = self.rewind_seconds {
+            options.rewind_seconds = rewind_seconds;
+        }
+        self.settings.apply(options)
+    }
+
+    // The settings a ROM runs with: its own section on top of the global ones in `options`,
+    // then the command line `args` again, which win over both. The quirks of `options` may have
+    // been picked in the loader since, they stay when `args` set them too.
+    // sha1 as Cartridge::rom_sha1 returns it
+    pub fn for_rom(&self, options: &Options, sha1: &str, args: &[String]) -> Options {
+        let mut rom_options = options.clone();
+        if let Some(settings) = self.roms.get(sha1) {
+            // checked in parse and main
+            settings.apply(&mut rom_options).ok();
+            rom_options.parse_args(args.iter().cloned()).ok();
+            if args.iter().any(|arg| arg == "--quirks") {
+                rom_options.quirks = options.quirks;
+            }
+        }
+        rom_options
+    }
+}
+
+// `--config <file>`, or the file in the user config directory
+pub fn config_path(args: &[String]) -> (Option<PathBuf>, bool) {
+    match args.iter().position(|arg| arg == "--config") {
+        Some(index) => (args.get(index + 1).map(PathBuf::from), true),
+        None => (
+            dirs::config_dir().map(|directory| directory.join("chip8").join("config.toml")),
+            false,
+        ),
+    }
+}
+
+#[cfg(test)]
+#[path = "./config_test.rs"]
+mod test;
@@ -0,0 +1,99 @@
+use super::*;
+use chip8_core::quirks::QUIRKS_PROFILES;
+use piston::input::Key;
+
+const CONFIG: &str = r#"
+rom_directories = ["roms/"]
+scale = 15
+rewind_seconds = 30
+instructions_per_frame = 12
+palette = "101010,F0F0F0"
+volume = 0.5
+
+[keys]
+5 = "Up"
+a = "numpad0"
+
+[roms.0123456789ABCDEF0123456789ABCDEF01234567]
+name = "pong"
+instructions_per_frame = 20
+quirks = "super-chip"
+keys = { 5 = "W" }
+"#;
+
+#[test]
+fn apply_test() {
+    let config = Config::parse(CONFIG).unwrap();
+    let mut options = Options::new();
+    config.apply(&mut options).unwrap();
+    assert_eq!(options.rom_paths, ["roms/"]);
+    assert_eq!(options.scale, 15);
+    assert_eq!(options.rewind_seconds, 30);
+    assert_eq!(options.instructions_per_frame, 12);
+    assert_eq!(
+        options.palette[0],
+        [16.0 / 255.0, 16.0 / 255.0, 16.0 / 255.0, 1.0]
+    );
+    assert_eq!(options.volume, 0.5);
+    assert_eq!(options.keys[5], Key::Up);
+    assert_eq!(options.keys[0xA], Key::NumPad0);
+    assert_eq!(options.keys[0], Key::X);
+
+    // the command line goes on top
+    options
+        .parse_args(vec!["--ipf".to_string(), "15".to_string()])
+        .unwrap();
+    assert_eq!(options.instructions_per_frame, 15);
+
+    let args = ["--ipf".to_string(), "15".to_string()];
+    let pong = config.for_rom(&options, "0123456789abcdef0123456789abcdef01234567", &args);
+    // the ROM section wins over the global settings but not over the command line
+    assert_eq!(pong.instructions_per_frame, 15);
+    assert_eq!(QUIRKS_PROFILES[pong.quirks].0, "SUPER-CHIP");
+    assert_eq!(pong.keys[5], Key::W);
+    assert_eq!(pong.keys[0xA], Key::NumPad0);
+    let other = config.for_rom(&options, "ffffffffffffffffffffffffffffffffffffffff", &args);
+    assert_eq!(other, options);
+}
+
+#[test]
+fn for_rom_quirks_test() {
+    let config = Config::parse(CONFIG).unwrap();
+    let sha1 = "0123456789abcdef0123456789abcdef01234567";
+    let mut options = Options::new();
+    // picked in the loader after `--quirks CHIP-8`
+    options.quirks = 3;
+    let args = ["--quirks".to_string(), "CHIP-8".to_string()];
+    assert_eq!(config.for_rom(&options, sha1, &args).quirks, 3);
+    assert_eq!(config.for_rom(&options, sha1, &[]).quirks, 2);
+}
+
+#[test]
+fn parse_errors_test() {
+    assert!(Config::parse("").is_ok());
+    assert!(Config::parse("quirks = \"CHIP-9\"").is_err());
+    assert!(Config::parse("volume = 2.0").is_err());
+    assert!(Config::parse("[keys]\n10 = \"A\"").is_err());
+    assert!(Config::parse("[keys]\n1 = \"Escape\"").is_err());
+    assert!(Config::parse("[roms.pong]\nquirks = \"CHIP-8\"").is_err());
+    assert!(
+        Config::parse("[roms.0123456789abcdef0123456789abcdef01234567]\nvolume = -1.0").is_err()
+    );
+    assert!(Config::parse("scale = \"big\"").is_err());
+}
+
+#[test]
+fn unknown_keys_test() {
+    assert!(Config::parse(CONFIG).unwrap().unknown_keys().is_empty());
+    let config = Config::parse(
+        "sacle = 3\nvolume = 0.5\n[roms.0123456789ABCDEF0123456789ABCDEF01234567]\nname = \"pong\"\nquirk = \"CHIP-8\"",
+    )
+    .unwrap();
+    assert_eq!(
+        config.unknown_keys(),
+        [
+            "sacle",
+            "roms.0123456789abcdef0123456789abcdef01234567.quirk"
+        ]
+    );
+}
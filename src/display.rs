@@ -1,20 +1,35 @@
-use crate::cartridge_reader::*;
-use crate::processor::Cpu;
-use crate::SIZE_SCALLER;
+use chip8_core::cartridge_reader::*;
+use chip8_core::debugger::Debugger;
+use chip8_core::processor::{Cpu, CpuError};
 use graphics::*;
 use opengl_graphics::{GlGraphics, GlyphCache, OpenGL};
 use piston::input::RenderArgs;
 
 const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
 const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
-const PIXEL_DIMENTION: f64 = SIZE_SCALLER as f64;
+// XO-CHIP pixels carry one bit per plane, each combination gets its own colour
+pub const PALETTE: [[f32; 4]; 4] = [BLACK, WHITE, [1.0, 0.4, 0.0, 1.0], [0.4, 0.13, 0.0, 1.0]];
 const FONT_SIZE: u32 = 32;
 const FONT_SIZE_SMALL: u32 = 20;
 const CHOOSE_GAME: &str = "CHOOSE GAME";
+const CRASHED: &str = "CRASHED";
+const PRESS_ESCAPE: &str = "PRESS ESC";
+const FONT_SIZE_DEBUG: u32 = 14;
+const DEBUG_LINE: f64 = 17.0;
+const DEBUG_LISTING_LINES: usize = 10;
+const DEBUG_DUMP_ROWS: usize = 6;
+// While debugging the game keeps its normal size in the top left corner,
+// registers and code go on the right, a dump of memory around I below
+pub const GAME_WIDTH: f64 = 640.0;
+pub const GAME_HEIGHT: f64 = 320.0;
+pub const DEBUG_WINDOW: [u32; 2] = [1040, 440];
 
 pub struct GameGraphics {
     gl: GlGraphics,
     pub draw: bool,
+    pub palette: [[f32; 4]; 4],
+    // shown at the bottom of the ROM loader, e.g. why the last game did not start
+    pub status: String,
 }
 
 impl GameGraphics {
@@ -22,32 +37,76 @@ impl GameGraphics {
         GameGraphics {
             gl: GlGraphics::new(OpenGL::V3_2),
             draw: true,
+            palette: PALETTE,
+            status: String::new(),
         }
     }
     pub fn render(&mut self, args: &RenderArgs, cpu: &Cpu) {
-        let pixels = cpu.read_pixels();
-        let square = Rectangle::new(WHITE);
+        let pixel_dimention = args.window_size[0] / cpu.resolution().0 as f64;
+        let palette = &self.palette;
         self.gl.draw(args.viewport(), |c, gl| {
-            clear(BLACK, gl);
+            clear(palette[0], gl);
+            draw_pixels(cpu, palette, pixel_dimention, c.transform, gl);
+        });
+    }
 
-            for (y, row) in pixels.into_iter().enumerate() {
-                for (x, pixel) in row.into_iter().enumerate() {
-                    let x = x as f64 * SIZE_SCALLER as f64;
-                    let y = y as f64 * SIZE_SCALLER as f64;
-                    if pixel != 0 {
-                        square.draw(
-                            [x, y, PIXEL_DIMENTION, PIXEL_DIMENTION],
-                            &DrawState::default(),
-                            c.transform,
-                            gl,
-                        );
-                    }
-                }
+    pub fn render_debugger(
+        &mut self,
+        args: &RenderArgs,
+        glyph: &mut GlyphCache,
+        cpu: &Cpu,
+        debugger: &Debugger,
+        cursor: usize,
+    ) {
+        let pixel_dimention = GAME_WIDTH / cpu.resolution().0 as f64;
+        let panel_x = GAME_WIDTH + 16.0;
+        let dump_y = GAME_HEIGHT + 24.0;
+        let panel = debug_panel(cpu, debugger, cursor);
+        let dump = memory_dump(cpu);
+        let palette = &self.palette;
+        self.gl.draw(args.viewport(), |c, gl| {
+            clear(palette[0], gl);
+            draw_pixels(cpu, palette, pixel_dimention, c.transform, gl);
+            Rectangle::new_border(WHITE, 0.5).draw(
+                [0.0, 0.0, GAME_WIDTH, GAME_HEIGHT],
+                &DrawState::default(),
+                c.transform,
+                gl,
+            );
+            for (line, row) in panel.iter().enumerate() {
+                let y = DEBUG_LINE * (line + 1) as f64;
+                text(
+                    WHITE,
+                    FONT_SIZE_DEBUG,
+                    row,
+                    glyph,
+                    c.transform.trans(panel_x, y),
+                    gl,
+                )
+                .unwrap();
+            }
+            for (line, row) in dump.iter().enumerate() {
+                let y = dump_y + DEBUG_LINE * line as f64;
+                text(
+                    WHITE,
+                    FONT_SIZE_DEBUG,
+                    row,
+                    glyph,
+                    c.transform.trans(8.0, y),
+                    gl,
+                )
+                .unwrap();
             }
         });
     }
 
-    pub fn draw_ui(&mut self, args: &RenderArgs, glyph: &mut GlyphCache, cartridge: &Cartridge) {
+    pub fn draw_ui(
+        &mut self,
+        args: &RenderArgs,
+        glyph: &mut GlyphCache,
+        cartridge: &Cartridge,
+        quirks_name: &str,
+    ) {
         if self.draw {
             let title_x = 144.0;
             let title_y = 50.0;
@@ -60,11 +119,20 @@ impl GameGraphics {
             let left_arrow_y = 180.0;
             let right_arrow_x = 512.0;
             let right_arrow_y = left_arrow_y;
-            let rom_name = cartridge.get_game_name();
+            let rom_name = &cartridge.get_game_name();
             let half_name = (rom_name.chars().count() as f64 / 2.0) * FONT_SIZE_SMALL as f64;
             let rom_x = 320.0 - half_name;
             let rom_y = left_arrow_y;
+            let quirks = format!("QUIRKS: {}", quirks_name);
+            let half_quirks = (quirks.chars().count() as f64 / 2.0) * FONT_SIZE_SMALL as f64 / 1.5;
+            let quirks_x = 320.0 - half_quirks;
+            let quirks_y = 260.0;
+            let status = self.status.to_uppercase();
+            let status_x = 16.0;
+            let status_y = 310.0;
+            let ui_scale = args.window_size[0] / GAME_WIDTH;
             self.gl.draw(args.viewport(), |c, gl| {
+                let c = c.scale(ui_scale, ui_scale);
                 clear(BLACK, gl);
                 text(
                     WHITE,
@@ -90,6 +158,24 @@ impl GameGraphics {
                     gl,
                 )
                 .unwrap();
+                text(
+                    WHITE,
+                    FONT_SIZE_SMALL,
+                    &quirks,
+                    glyph,
+                    c.transform.trans(quirks_x, quirks_y),
+                    gl,
+                )
+                .unwrap();
+                text(
+                    WHITE,
+                    FONT_SIZE_DEBUG,
+                    &status,
+                    glyph,
+                    c.transform.trans(status_x, status_y),
+                    gl,
+                )
+                .unwrap();
                 text(
                     WHITE,
                     FONT_SIZE,
@@ -111,4 +197,160 @@ impl GameGraphics {
             });
         }
     }
+
+    pub fn draw_crash(&mut self, args: &RenderArgs, glyph: &mut GlyphCache, error: &CpuError) {
+        if self.draw {
+            let title_x = 208.0;
+            let title_y = 50.0;
+            let error = error.to_string().to_uppercase();
+            let half_error = (error.chars().count() as f64 / 2.0) * FONT_SIZE_SMALL as f64 / 1.5;
+            let error_x = (320.0 - half_error).max(0.0);
+            let error_y = 180.0;
+            let escape_x = 256.0;
+            let escape_y = 280.0;
+            let ui_scale = args.window_size[0] / GAME_WIDTH;
+            self.gl.draw(args.viewport(), |c, gl| {
+                let c = c.scale(ui_scale, ui_scale);
+                clear(BLACK, gl);
+                text(
+                    WHITE,
+                    FONT_SIZE,
+                    CRASHED,
+                    glyph,
+                    c.transform.trans(title_x, title_y),
+                    gl,
+                )
+                .unwrap();
+                text(
+                    WHITE,
+                    FONT_SIZE_SMALL,
+                    &error,
+                    glyph,
+                    c.transform.trans(error_x, error_y),
+                    gl,
+                )
+                .unwrap();
+                text(
+                    WHITE,
+                    FONT_SIZE_SMALL,
+                    PRESS_ESCAPE,
+                    glyph,
+                    c.transform.trans(escape_x, escape_y),
+                    gl,
+                )
+                .unwrap();
+            });
+        }
+    }
+}
+
+fn draw_pixels(
+    cpu: &Cpu,
+    palette: &[[f32; 4]; 4],
+    pixel_dimention: f64,
+    transform: math::Matrix2d,
+    gl: &mut GlGraphics,
+) {
+    let (width, height) = cpu.resolution();
+    for (y, row) in cpu.read_pixels().iter().take(height).enumerate() {
+        for (x, pixel) in row.iter().take(width).enumerate() {
+            let x = x as f64 * pixel_dimention;
+            let y = y as f64 * pixel_dimention;
+            if *pixel != 0 {
+                let square = Rectangle::new(palette[*pixel as usize & 0b11]);
+                square.draw(
+                    [x, y, pixel_dimention, pixel_dimention],
+                    &DrawState::default(),
+                    transform,
+                    gl,
+                );
+            }
+        }
+    }
+}
+
+// Registers, stack and the code from the cursor on.
+// In the listing > marks the program counter, * a breakpoint and - the cursor
+fn debug_panel(cpu: &Cpu, debugger: &Debugger, cursor: usize) -> Vec<String> {
+    let mut panel = Vec::new();
+    let state = if debugger.paused { "PAUSED" } else { "RUNNING" };
+    panel.push(format!(
+        "{}  PC {:04X}  I {:04X}",
+        state,
+        cpu.program_counter(),
+        cpu.i()
+    ));
+    panel.push(format!(
+        "SP {}  DT {:02X}  ST {:02X}",
+        cpu.stack_pointer(),
+        cpu.delay_timer(),
+        cpu.check_sound_timer()
+    ));
+    for (row, registers) in cpu.registers().chunks(4).enumerate() {
+        let registers: Vec<String> = registers
+            .iter()
+            .enumerate()
+            .map(|(column, value)| format!("V{:X} {:02X}", row * 4 + column, value))
+            .collect();
+        panel.push(registers.join("  "));
+    }
+    let stack: Vec<String> = cpu.stack()[..cpu.stack_pointer() as usize]
+        .iter()
+        .map(|address| format!("{:03X}", address))
+        .collect();
+    panel.push(format!("STACK {}", stack.join(" ")));
+    match debugger.watch_hit {
+        Some(hit) => panel.push(
+            format!(
+                "WATCH {:?} {:04X} BY {:04X}",
+                hit.access, hit.address, hit.program_counter
+            )
+            .to_uppercase(),
+        ),
+        None => panel.push(format!("WATCHPOINTS {}", cpu.watchpoints().len())),
+    }
+    panel.push(String::new());
+
+    let mut address = cursor;
+    for line in 0..DEBUG_LISTING_LINES {
+        let (mnemonic, size) = Debugger::disassemble(cpu, address);
+        let pc = if address == cpu.program_counter() {
+            '>'
+        } else {
+            ' '
+        };
+        let breakpoint = if debugger.is_breakpoint(address) {
+            '*'
+        } else {
+            ' '
+        };
+        let selected = if line == 0 { '-' } else { ' ' };
+        panel.push(format!(
+            "{}{}{} {:04X}  {}",
+            selected, pc, breakpoint, address, mnemonic
+        ));
+        address += size;
+    }
+    panel.push(String::new());
+    panel.push("SPACE RUN/PAUSE  N STEP  O OVER".to_string());
+    panel.push("B BREAKPOINT  G RUN TO  F12 EXIT".to_string());
+    panel.push("T WATCH WRITES AT I".to_string());
+    panel
+}
+
+// 16 bytes per row, starting one row above the one I points into
+fn memory_dump(cpu: &Cpu) -> Vec<String> {
+    let memory = cpu.memory();
+    let start = (cpu.i() as usize & !0xF).saturating_sub(0x10);
+    let start = start.min(memory.len() - DEBUG_DUMP_ROWS * 16);
+    (0..DEBUG_DUMP_ROWS)
+        .map(|row| {
+            let address = start + row * 16;
+            let bytes: Vec<String> = memory[address..address + 16]
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            format!("{:04X}  {}", address, bytes.join(" "))
+        })
+        .collect()
 }
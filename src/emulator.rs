@@ -1,18 +1,26 @@
+use chip8_core::processor::CpuError;
+use chip8_core::quirks::QUIRKS_PROFILES;
+
 #[derive(PartialEq)]
 pub struct Emulator {
     pub emulator_state: EmulatorState,
+    quirks_index: usize,
 }
 
 #[derive(PartialEq)]
 pub enum EmulatorState {
     InGame,
     InRomLoader,
+    // InGame with the debugger panel open
+    Debugging,
+    Crashed(CpuError),
 }
 
 impl Emulator {
-    pub fn new() -> Emulator {
+    pub fn new(quirks_index: usize) -> Emulator {
         Emulator {
             emulator_state: EmulatorState::InRomLoader,
+            quirks_index,
         }
     }
     pub fn switch_state(&mut self) {
@@ -22,4 +30,21 @@ impl Emulator {
             EmulatorState::InRomLoader
         }
     }
+    // index into QUIRKS_PROFILES
+    pub fn quirks_index(&self) -> usize {
+        self.quirks_index
+    }
+    pub fn quirks_name(&self) -> &str {
+        QUIRKS_PROFILES[self.quirks_index].0
+    }
+    pub fn next_quirks(&mut self) {
+        self.quirks_index = (self.quirks_index + 1) % QUIRKS_PROFILES.len();
+    }
+    pub fn previous_quirks(&mut self) {
+        self.quirks_index = if self.quirks_index == 0 {
+            QUIRKS_PROFILES.len() - 1
+        } else {
+            self.quirks_index - 1
+        };
+    }
 }
@@ -1,13 +1,47 @@
 use piston::input::*;
 
+// The keyboard key for every CHIP-8 key, 0 to F
+pub const DEFAULT_BINDINGS: [Key; 16] = [
+    Key::X,
+    Key::D1,
+    Key::D2,
+    Key::D3,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Z,
+    Key::C,
+    Key::D4,
+    Key::R,
+    Key::F,
+    Key::V,
+];
+const NUMPAD: [Key; 10] = [
+    Key::NumPad0,
+    Key::NumPad1,
+    Key::NumPad2,
+    Key::NumPad3,
+    Key::NumPad4,
+    Key::NumPad5,
+    Key::NumPad6,
+    Key::NumPad7,
+    Key::NumPad8,
+    Key::NumPad9,
+];
+
 pub struct Keypad {
     key_map: [bool; 16],
+    pub bindings: [Key; 16],
 }
 
 impl Keypad {
     pub fn new() -> Keypad {
         Keypad {
             key_map: [false; 16],
+            bindings: DEFAULT_BINDINGS,
         }
     }
     pub fn map_keys(&self) -> &[bool; 16] {
@@ -15,46 +49,42 @@ impl Keypad {
     }
 
     pub fn presse_key(&mut self, key: Key) {
-        match key {
-            Key::X => self.key_map[0x0] = true,
-            Key::D1 => self.key_map[0x1] = true,
-            Key::D2 => self.key_map[0x2] = true,
-            Key::D3 => self.key_map[0x3] = true,
-            Key::Q => self.key_map[0x4] = true,
-            Key::W => self.key_map[0x5] = true,
-            Key::E => self.key_map[0x6] = true,
-            Key::A => self.key_map[0x7] = true,
-            Key::S => self.key_map[0x8] = true,
-            Key::D => self.key_map[0x9] = true,
-            Key::Z => self.key_map[0xA] = true,
-            Key::C => self.key_map[0xB] = true,
-            Key::D4 => self.key_map[0xC] = true,
-            Key::R => self.key_map[0xD] = true,
-            Key::F => self.key_map[0xE] = true,
-            Key::V => self.key_map[0xF] = true,
-            _ => {}
+        for (chip8_key, binding) in self.bindings.iter().enumerate() {
+            if *binding == key {
+                self.key_map[chip8_key] = true;
+            }
         }
     }
 
     pub fn release_key(&mut self, key: Key) {
-        match key {
-            Key::X => self.key_map[0x0] = false,
-            Key::D1 => self.key_map[0x1] = false,
-            Key::D2 => self.key_map[0x2] = false,
-            Key::D3 => self.key_map[0x3] = false,
-            Key::Q => self.key_map[0x4] = false,
-            Key::W => self.key_map[0x5] = false,
-            Key::E => self.key_map[0x6] = false,
-            Key::A => self.key_map[0x7] = false,
-            Key::S => self.key_map[0x8] = false,
-            Key::D => self.key_map[0x9] = false,
-            Key::Z => self.key_map[0xA] = false,
-            Key::C => self.key_map[0xB] = false,
-            Key::D4 => self.key_map[0xC] = false,
-            Key::R => self.key_map[0xD] = false,
-            Key::F => self.key_map[0xE] = false,
-            Key::V => self.key_map[0xF] = false,
-            _ => {}
+        for (chip8_key, binding) in self.bindings.iter().enumerate() {
+            if *binding == key {
+                self.key_map[chip8_key] = false;
+            }
         }
     }
 }
+
+// Letters, digits and punctuation by themselves, plus Space, Return, Tab,
+// Up, Down, Left, Right and NumPad0-NumPad9, ignoring case
+pub fn parse_key(name: &str) -> Option<Key> {
+    let name = name.to_ascii_lowercase();
+    let key = match name.as_str() {
+        "space" => Key::Space,
+        "return" | "enter" => Key::Return,
+        "tab" => Key::Tab,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        _ => match name.strip_prefix("numpad") {
+            Some(digit) => *NUMPAD.get(digit.parse::<usize>().ok()?)?,
+            None if name.chars().count() == 1 => Key::from(name.chars().next()? as u32),
+            None => return None,
+        },
+    };
+    if key == Key::Unknown {
+        return None;
+    }
+    Some(key)
+}
@@ -1,96 +1,305 @@
 mod audio;
-mod cartridge_reader;
+mod config;
 mod display;
 mod emulator;
 mod keypad;
-mod processor;
+mod movies;
+mod options;
+mod save_slots;
 use audio::*;
-use cartridge_reader::*;
+use chip8_core::cartridge_reader::*;
+use chip8_core::debugger::Debugger;
+use chip8_core::movie::Movie;
+use chip8_core::processor::*;
+use chip8_core::quirks::*;
+use chip8_core::random::Random;
+use chip8_core::rewind::Rewind;
+use chip8_core::trace::Tracer;
+use chip8_core::watchpoint::Access;
+use config::{config_path, Config};
 use display::*;
 use emulator::*;
 use keypad::*;
-use processor::*;
+use movies::MovieMode;
+use options::{Options, USAGE};
 
-use std::time::{Duration, Instant};
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::exit;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlyphCache, OpenGL, TextureSettings};
 use piston::event_loop::{EventSettings, Events};
 use piston::input::*;
-use piston::window::WindowSettings;
+use piston::window::{AdvancedWindow, WindowSettings};
 
 use rodio::OutputStream;
 
-const WIDTH: usize = 64;
-const HEIGHT: usize = 32;
-const RAM: usize = 4096;
-const PROGRAM_START: usize = 0x200;
-const SIZE_SCALLER: u32 = 10;
-const TIMER_RATE: u64 = 16666; // 60 Hz
-const EMULATOR_RATE: u64 = 1851; //540 Hz
+use chip8_core::{HEIGHT, RAM, TIMER_RATE, WIDTH};
+
+// Without `--seed` CXNN produces different numbers on every run
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// `--trace <file>` writes every executed instruction to a file, filtered by the other --trace options
+fn tracer(options: &Options) -> Option<Tracer> {
+    let path = options.trace_path.as_ref()?;
+    match File::create(path) {
+        Ok(file) => Some(Tracer::new(
+            Box::new(BufWriter::new(file)),
+            options.trace_format,
+            options.trace_filter.clone(),
+        )),
+        Err(error) => {
+            eprintln!("Failed to create {}: {}", path, error);
+            exit(1);
+        }
+    }
+}
+
+fn finish_trace(cpu: &mut Cpu) {
+    if let Some(Err(error)) = cpu.take_tracer().map(Tracer::finish) {
+        eprintln!("Failed to write the trace: {}", error);
+    }
+}
+
+// Back to the size of the game after the debugger made the window bigger
+fn resize_window(window: &mut Window, scale: u32) {
+    window.set_size([WIDTH as u32 * scale, HEIGHT as u32 * scale]);
+}
+
+// Pauses the game in the debugger, which needs a bigger window for its panel.
+// Returns where the listing starts
+fn open_debugger(
+    window: &mut Window,
+    debugger: &mut Debugger,
+    emulator: &mut Emulator,
+    cpu: &Cpu,
+) -> usize {
+    debugger.pause();
+    window.set_size(DEBUG_WINDOW);
+    emulator.emulator_state = EmulatorState::Debugging;
+    cpu.program_counter()
+}
+
+// The options of the ROM in the cartridge, with the quirks picked in the loader
+// in place of the global ones. The loader keeps its pick for the next ROM
+fn rom_options(
+    config: &Config,
+    options: &Options,
+    args: &[String],
+    emulator: &Emulator,
+    cartridge: &Cartridge,
+) -> Options {
+    let mut options = options.clone();
+    options.quirks = emulator.quirks_index();
+    config.for_rom(&options, &cartridge.rom_sha1(), args)
+}
+
+fn apply_rom_options(
+    options: &Options,
+    game_graphics: &mut GameGraphics,
+    keypad: &mut Keypad,
+    audio: &mut Audio,
+) {
+    game_graphics.palette = options.palette;
+    keypad.bindings = options.keys;
+    audio.frequency = options.buzzer_frequency;
+    audio.volume = options.volume;
+}
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = match config_path(&args) {
+        (Some(path), required) => {
+            let config = Config::load(&path, required).unwrap_or_else(|error| {
+                eprintln!("chip8: {}: {}", path.display(), error);
+                exit(2);
+            });
+            for key in config.unknown_keys() {
+                eprintln!("chip8: {}: unknown key `{}` ignored", path.display(), key);
+            }
+            config
+        }
+        (None, _) => Config::default(),
+    };
+    let mut options = Options::new();
+    if let Err(error) = config.apply(&mut options) {
+        eprintln!("chip8: config: {}", error);
+        exit(2);
+    }
+    if let Err(error) = options.parse_args(args.iter().cloned()) {
+        eprintln!("chip8: {}", error);
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let seed = options.seed.unwrap_or_else(random_seed);
+    let mut instructions_per_frame = options.instructions_per_frame;
+    let scale = options.scale;
     let opengl = OpenGL::V3_2;
 
-    let window_width: u32 = WIDTH as u32 * SIZE_SCALLER;
-    let window_height: u32 = HEIGHT as u32 * SIZE_SCALLER;
+    let window_width: u32 = WIDTH as u32 * scale;
+    let window_height: u32 = HEIGHT as u32 * scale;
 
     let mut window: Window = WindowSettings::new("CHIP8", [window_width, window_height])
         .graphics_api(opengl)
         .exit_on_esc(false)
         .resizable(false)
+        .fullscreen(options.fullscreen)
         .build()
         .unwrap();
 
-    let mut glyph = GlyphCache::new("assets/VCR_OSD_MONO.ttf", (), TextureSettings::new()).unwrap();
+    let mut glyph =
+        GlyphCache::new(&options.font_path, (), TextureSettings::new()).unwrap_or_else(|error| {
+            eprintln!("Failed to load {}: {}", options.font_path, error);
+            exit(1);
+        });
 
-    let mut cpu = Cpu::new();
-    let mut cartridge = Cartridge::new();
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.set_random(Random::new(seed));
+    cpu.set_tracer(tracer(&options));
+    let mut cartridge = Cartridge::from_paths(&options.rom_paths).unwrap_or_else(|error| {
+        eprintln!("{}: {}", options.rom_paths.join(", "), error);
+        exit(1);
+    });
     let mut game_graphics = GameGraphics::new();
     let mut keypad = Keypad::new();
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let mut audio = Audio::new(640.0, stream_handle);
-    let mut emulator = Emulator::new();
+    let mut audio = Audio::new(options.buzzer_frequency, options.mute, stream_handle);
+    let mut emulator = Emulator::new(options.quirks);
+    apply_rom_options(&options, &mut game_graphics, &mut keypad, &mut audio);
 
     let mut last_tick = Instant::now();
+    let mut shift = false;
+    let mut rewind = Rewind::new(options.rewind_seconds * 60);
+    let mut rewinding = false;
+    let mut movie_mode = MovieMode::Off;
+    let mut debugger = Debugger::new();
+    let mut cursor = 0;
+
+    // a single ROM starts right away, Escape still leads to the loader
+    if options.single_rom() {
+        if let Err(error) = cartridge.game_to_rom() {
+            eprintln!("{}: {}", cartridge.get_game_name(), error);
+            exit(1);
+        }
+        let rom_options = rom_options(&config, &options, &args, &emulator, &cartridge);
+        apply_rom_options(&rom_options, &mut game_graphics, &mut keypad, &mut audio);
+        instructions_per_frame = rom_options.instructions_per_frame;
+        cpu.set_quirks(QUIRKS_PROFILES[rom_options.quirks].1);
+        cpu.load_rom(&cartridge.rom);
+        emulator.switch_state();
+        if options.paused {
+            cursor = open_debugger(&mut window, &mut debugger, &mut emulator, &cpu);
+        }
+    }
 
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
         match emulator.emulator_state {
             EmulatorState::InRomLoader => {
                 if let Some(args) = e.render_args() {
-                    game_graphics.draw_ui(&args, &mut glyph, &cartridge);
+                    game_graphics.draw_ui(&args, &mut glyph, &cartridge, emulator.quirks_name());
                     game_graphics.draw = false;
                 }
                 if let Some(Button::Keyboard(key)) = e.press_args() {
                     match key {
                         Key::A => {
                             cartridge.previous_game();
+                            game_graphics.status.clear();
                             game_graphics.draw = true;
                         }
                         Key::Left => {
                             cartridge.previous_game();
+                            game_graphics.status.clear();
                             game_graphics.draw = true;
                         }
                         Key::D => {
                             cartridge.next_game();
+                            game_graphics.status.clear();
                             game_graphics.draw = true;
                         }
                         Key::Right => {
                             cartridge.next_game();
+                            game_graphics.status.clear();
                             game_graphics.draw = true;
                         }
-                        Key::Return => {
-                            cartridge.game_to_rom();
-                            cpu.load_rom(&cartridge);
-                            emulator.switch_state();
+                        Key::Up => {
+                            emulator.previous_quirks();
+                            game_graphics.draw = true;
                         }
-                        Key::Space => {
-                            cartridge.game_to_rom();
-                            cpu.load_rom(&cartridge);
-                            emulator.switch_state();
+                        Key::Down => {
+                            emulator.next_quirks();
+                            game_graphics.draw = true;
+                        }
+                        // M records a movie of the session, P plays the recorded one back
+                        Key::Return | Key::Space | Key::M | Key::P => {
+                            match cartridge.game_to_rom() {
+                                Ok(()) => {
+                                    game_graphics.status.clear();
+                                    let rom_options = rom_options(
+                                        &config, &options, &args, &emulator, &cartridge,
+                                    );
+                                    apply_rom_options(
+                                        &rom_options,
+                                        &mut game_graphics,
+                                        &mut keypad,
+                                        &mut audio,
+                                    );
+                                    instructions_per_frame = rom_options.instructions_per_frame;
+                                    let quirks = QUIRKS_PROFILES[rom_options.quirks].1;
+                                    let mode = match key {
+                                        Key::M => Some(MovieMode::Recording(Movie::new(
+                                            &cartridge.rom,
+                                            cpu.random().state(),
+                                            quirks,
+                                            instructions_per_frame as u16,
+                                        ))),
+                                        Key::P => movies::load(&cartridge)
+                                            .map(|movie| MovieMode::Playing { movie, frame: 0 }),
+                                        _ => Some(MovieMode::Off),
+                                    };
+                                    if let Some(mode) = mode {
+                                        cpu.set_quirks(quirks);
+                                        cpu.load_rom(&cartridge.rom);
+                                        if let MovieMode::Playing { movie, .. } = &mode {
+                                            let tracer = cpu.take_tracer();
+                                            cpu = movie.start(&cartridge.rom);
+                                            cpu.set_tracer(tracer);
+                                        }
+                                        movie_mode = mode;
+                                        rewind.clear();
+                                        emulator.switch_state();
+                                        if options.paused && !movie_mode.is_active() {
+                                            cursor = open_debugger(
+                                                &mut window,
+                                                &mut debugger,
+                                                &mut emulator,
+                                                &cpu,
+                                            );
+                                        }
+                                    }
+                                }
+                                Err(error) => {
+                                    game_graphics.status =
+                                        format!("{}: {}", cartridge.get_game_name(), error);
+                                    eprintln!("{}", game_graphics.status);
+                                    game_graphics.draw = true;
+                                }
+                            }
                         }
                         Key::Escape => {
+                            finish_trace(&mut cpu);
                             return;
                         }
                         _ => {}
@@ -99,10 +308,59 @@ fn main() {
             }
 
             EmulatorState::InGame => {
-                if last_tick.elapsed() >= Duration::from_nanos(EMULATOR_RATE) {
-                    cpu.load_key_map(keypad.map_keys());
+                if last_tick.elapsed() >= Duration::from_micros(TIMER_RATE) {
+                    let mut frame_instructions = instructions_per_frame;
+                    match &mut movie_mode {
+                        MovieMode::Playing { movie, frame } => match movie.key_map(*frame) {
+                            Some(key_map) => {
+                                cpu.load_key_map(&key_map);
+                                frame_instructions = movie.instructions_per_frame as usize;
+                                *frame += 1;
+                            }
+                            None => {
+                                eprintln!("Movie finished, the keyboard is back in control");
+                                movie_mode = MovieMode::Off;
+                                cpu.load_key_map(keypad.map_keys());
+                            }
+                        },
+                        MovieMode::Recording(movie) => {
+                            movie.record(keypad.map_keys());
+                            cpu.load_key_map(keypad.map_keys());
+                        }
+                        MovieMode::Off => cpu.load_key_map(keypad.map_keys()),
+                    }
 
-                    cpu.run_next_instruction();
+                    if rewinding {
+                        rewind.step_back(&mut cpu, &cartridge.rom);
+                    } else {
+                        // a frame cut short by a watchpoint would not match the movie
+                        let result = if movie_mode.is_active() {
+                            cpu.run_frame_ignoring_watchpoints(frame_instructions)
+                        } else {
+                            cpu.run_frame(frame_instructions)
+                        };
+                        match result {
+                            Ok(StepOutcome::Halted) => {
+                                movies::finish(&mut movie_mode, &cartridge);
+                                cpu.reset();
+                                emulator.emulator_state = EmulatorState::InRomLoader;
+                                game_graphics.draw = true;
+                            }
+                            // watchpoints set in the debugger keep pausing after it is closed
+                            Ok(StepOutcome::Watchpoint(hit)) => {
+                                rewind.push(&cpu, &cartridge.rom);
+                                cursor =
+                                    open_debugger(&mut window, &mut debugger, &mut emulator, &cpu);
+                                debugger.watch_hit = Some(hit);
+                            }
+                            Ok(_) => rewind.push(&cpu, &cartridge.rom),
+                            Err(error) => {
+                                movies::finish(&mut movie_mode, &cartridge);
+                                emulator.emulator_state = EmulatorState::Crashed(error);
+                                game_graphics.draw = true;
+                            }
+                        }
+                    }
 
                     last_tick = Instant::now();
                 }
@@ -110,6 +368,11 @@ fn main() {
                     game_graphics.render(&args, &cpu);
                 }
                 if let Some(Button::Keyboard(key)) = e.release_args() {
+                    match key {
+                        Key::LShift | Key::RShift => shift = false,
+                        Key::Backspace => rewinding = false,
+                        _ => {}
+                    }
                     keypad.release_key(key);
                 }
                 audio.play(&cpu);
@@ -117,16 +380,129 @@ fn main() {
                 if let Some(Button::Keyboard(key)) = e.press_args() {
                     match key {
                         Key::Escape => {
+                            movies::finish(&mut movie_mode, &cartridge);
                             cpu.reset();
                             emulator.emulator_state = EmulatorState::InRomLoader;
                             game_graphics.draw = true;
                         }
-                        _ => {
-                            keypad.presse_key(key);
+                        Key::LShift | Key::RShift => shift = true,
+                        Key::F12 if !movie_mode.is_active() => {
+                            cursor = open_debugger(&mut window, &mut debugger, &mut emulator, &cpu);
                         }
+                        // hold to step back through the last --rewind seconds
+                        Key::Backspace if !movie_mode.is_active() => rewinding = true,
+                        _ => match save_slots::slot_for_key(key) {
+                            Some(_) if movie_mode.is_active() => {
+                                eprintln!("Save states are disabled during movies")
+                            }
+                            // Shift+F1-F10 saves, F1-F10 loads
+                            Some(slot) if shift => save_slots::save(&cpu, &cartridge, slot),
+                            Some(slot) => save_slots::load(&mut cpu, &cartridge, slot),
+                            None => keypad.presse_key(key),
+                        },
                     }
                 }
             }
+
+            EmulatorState::Debugging => {
+                let was_paused = debugger.paused;
+                let mut stepped = false;
+                let mut result = None;
+                if last_tick.elapsed() >= Duration::from_micros(TIMER_RATE) {
+                    cpu.load_key_map(keypad.map_keys());
+                    result = Some(debugger.run_frame(&mut cpu, instructions_per_frame));
+                    last_tick = Instant::now();
+                }
+                if let Some(args) = e.render_args() {
+                    game_graphics.render_debugger(&args, &mut glyph, &cpu, &debugger, cursor);
+                }
+                if let Some(Button::Keyboard(key)) = e.release_args() {
+                    keypad.release_key(key);
+                }
+                audio.play(&cpu);
+
+                if let Some(Button::Keyboard(key)) = e.press_args() {
+                    match key {
+                        Key::F12 => {
+                            resize_window(&mut window, scale);
+                            emulator.emulator_state = EmulatorState::InGame;
+                        }
+                        Key::Escape => {
+                            resize_window(&mut window, scale);
+                            cpu.reset();
+                            emulator.emulator_state = EmulatorState::InRomLoader;
+                            game_graphics.draw = true;
+                        }
+                        Key::Space => {
+                            if debugger.paused {
+                                debugger.resume();
+                            } else {
+                                debugger.pause();
+                            }
+                        }
+                        Key::N => {
+                            result = Some(debugger.step(&mut cpu));
+                            stepped = true;
+                        }
+                        Key::O => {
+                            result = Some(debugger.step_over(&mut cpu));
+                            stepped = true;
+                        }
+                        Key::B => debugger.toggle_breakpoint(cursor),
+                        Key::G => debugger.run_to(cursor),
+                        Key::T => {
+                            let i = cpu.i() as usize;
+                            let watched = cpu.watchpoints().iter().any(|watchpoint| {
+                                watchpoint.range == (i..i + 1) && watchpoint.access == Access::Write
+                            });
+                            if watched {
+                                cpu.remove_watchpoint(i..i + 1, Access::Write);
+                            } else {
+                                cpu.add_watchpoint(i..i + 1, Access::Write);
+                            }
+                        }
+                        Key::Up => cursor = cursor.saturating_sub(2),
+                        Key::Down => {
+                            cursor = (cursor + Debugger::disassemble(&cpu, cursor).1).min(RAM - 2)
+                        }
+                        _ => keypad.presse_key(key),
+                    }
+                }
+
+                // follow the program counter whenever execution stops
+                if debugger.paused && (!was_paused || stepped) {
+                    cursor = cpu.program_counter();
+                }
+                match result {
+                    Some(Ok(StepOutcome::Halted)) => {
+                        resize_window(&mut window, scale);
+                        cpu.reset();
+                        emulator.emulator_state = EmulatorState::InRomLoader;
+                        game_graphics.draw = true;
+                    }
+                    Some(Err(error)) => {
+                        resize_window(&mut window, scale);
+                        emulator.emulator_state = EmulatorState::Crashed(error);
+                        game_graphics.draw = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            EmulatorState::Crashed(error) => {
+                if let Some(args) = e.render_args() {
+                    game_graphics.draw_crash(&args, &mut glyph, &error);
+                    game_graphics.draw = false;
+                }
+                if let Some(Button::Keyboard(Key::Escape)) = e.press_args() {
+                    cpu.reset();
+                    emulator.emulator_state = EmulatorState::InRomLoader;
+                    game_graphics.draw = true;
+                }
+            }
         }
     }
+    // the window was closed, possibly in the middle of a recording
+    movies::finish(&mut movie_mode, &cartridge);
+    finish_trace(&mut cpu);
 }
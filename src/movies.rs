@@ -0,0 +1,56 @@
+use std::fs::{create_dir_all, read, write};
+
+use chip8_core::cartridge_reader::Cartridge;
+use chip8_core::movie::Movie;
+
+const MOVIE_DIRECTORY: &str = "movies";
+
+pub enum MovieMode {
+    Off,
+    Recording(Movie),
+    Playing { movie: Movie, frame: usize },
+}
+
+impl MovieMode {
+    // Rewind and save states would make the recording differ from what was played
+    pub fn is_active(&self) -> bool {
+        !matches!(self, MovieMode::Off)
+    }
+}
+
+fn movie_path(cartridge: &Cartridge) -> String {
+    format!("{}/{}.movie", MOVIE_DIRECTORY, cartridge.get_game_name())
+}
+
+pub fn load(cartridge: &Cartridge) -> Option<Movie> {
+    let path = movie_path(cartridge);
+    let movie = match read(&path).map(|bytes| Movie::from_bytes(&bytes)) {
+        Ok(Ok(movie)) => movie,
+        Ok(Err(error)) => {
+            eprintln!("Failed to load {}: {}", path, error);
+            return None;
+        }
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path, error);
+            return None;
+        }
+    };
+    if !movie.is_for_rom(&cartridge.rom) {
+        eprintln!("Failed to load {}: recorded with another rom", path);
+        return None;
+    }
+    eprintln!("Playing {}", path);
+    Some(movie)
+}
+
+// Writes the movie out when one was being recorded
+pub fn finish(movie_mode: &mut MovieMode, cartridge: &Cartridge) {
+    if let MovieMode::Recording(movie) = movie_mode {
+        let path = movie_path(cartridge);
+        match create_dir_all(MOVIE_DIRECTORY).and_then(|_| write(&path, movie.to_bytes())) {
+            Ok(()) => eprintln!("Saved {} frames to {}", movie.len(), path),
+            Err(error) => eprintln!("Failed to save {}: {}", path, error),
+        }
+    }
+    *movie_mode = MovieMode::Off;
+}
@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use chip8_core::quirks::QUIRKS_PROFILES;
+use chip8_core::trace::{TraceFilter, TraceFormat};
+use piston::input::Key;
+
+use crate::display::PALETTE;
+use crate::keypad::DEFAULT_BINDINGS;
+
+pub const USAGE: &str = "usage: chip8 [options] [rom files or directories]
+Without a ROM the loader lists the games in assets/, with a single file the game starts right away.
+Options given here win over the config file, its per-ROM sections included.
+  --config <file>           config file, chip8/config.toml in the user config directory by default
+  --scale <n>               window pixels per CHIP-8 pixel, 10 by default
+  --fullscreen              fill the whole screen
+  --ipf <n>                 instructions per frame, 9 by default (540 Hz)
+  --quirks <profile>        CHIP-8, COSMAC VIP, SUPER-CHIP or XO-CHIP, the loader can change it
+  --palette <colors>        comma separated hex colors: background, foreground and optionally
+                            the XO-CHIP second plane and both planes, e.g. 000000,FFFFFF
+  --buzzer <hz>             frequency of the buzzer, 640 by default
+  --mute                    no sound
+  --paused                  start games paused in the debugger
+  --rewind <seconds>        how far Backspace steps back, 10 by default, 0 turns rewind off
+  --font <file>             font for the menus, assets/VCR_OSD_MONO.ttf by default
+  --seed <n>                seed for CXNN, random by default
+  --trace <file>            write every executed instruction to a file
+  --trace-binary            write the trace in the compact binary format
+  --trace-addresses <range> only trace instructions in a hex range, e.g. 200-2FF
+  --trace-classes <list>    only trace opcode classes, e.g. 8,D
+  --trace-cycles <range>    only trace a range of cycles, e.g. 0-5000
+  -h, --help                show this help";
+
+// Everything the emulator can be started with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    // directories and files the loader picks games from, a single file starts right away
+    pub rom_paths: Vec<String>,
+    pub font_path: String,
+    pub scale: u32,
+    pub fullscreen: bool,
+    pub instructions_per_frame: usize,
+    // index into QUIRKS_PROFILES
+    pub quirks: usize,
+    pub palette: [[f32; 4]; 4],
+    pub buzzer_frequency: f32,
+    pub volume: f32,
+    pub mute: bool,
+    pub paused: bool,
+    pub rewind_seconds: usize,
+    pub keys: [Key; 16],
+    pub seed: Option<u64>,
+    pub trace_path: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_filter: TraceFilter,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            rom_paths: vec!["assets/".to_string()],
+            font_path: "assets/VCR_OSD_MONO.ttf".to_string(),
+            scale: 10,
+            fullscreen: false,
+            instructions_per_frame: 9, //540 Hz
+            quirks: 0,
+            palette: PALETTE,
+            buzzer_frequency: 640.0,
+            volume: 0.2,
+            mute: false,
+            paused: false,
+            rewind_seconds: 10,
+            keys: DEFAULT_BINDINGS,
+            seed: None,
+            trace_path: None,
+            trace_format: TraceFormat::Text,
+            trace_filter: TraceFilter::default(),
+            help: false,
+        }
+    }
+
+    // Only the options given change, the rest keeps what the config file set
+    pub fn parse_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), String> {
+        let mut rom_paths = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scale" => self.scale = parsed(&mut args, &arg, |value| value.parse().ok())?,
+                "--fullscreen" => self.fullscreen = true,
+                "--ipf" => {
+                    self.instructions_per_frame =
+                        parsed(&mut args, &arg, |value| value.parse().ok())?
+                }
+                "--quirks" => self.quirks = parsed(&mut args, &arg, quirks_index)?,
+                "--palette" => self.palette = parsed(&mut args, &arg, parse_palette)?,
+                "--buzzer" => {
+                    self.buzzer_frequency = parsed(&mut args, &arg, |value| value.parse().ok())?
+                }
+                "--mute" => self.mute = true,
+                "--paused" => self.paused = true,
+                "--rewind" => {
+                    self.rewind_seconds = parsed(&mut args, &arg, |value| value.parse().ok())?
+                }
+                "--font" => self.font_path = parsed(&mut args, &arg, |value| Some(value.into()))?,
+                "--seed" => self.seed = Some(parsed(&mut args, &arg, |value| value.parse().ok())?),
+                "--trace" => {
+                    self.trace_path = Some(parsed(&mut args, &arg, |value| Some(value.into()))?)
+                }
+                "--trace-binary" => self.trace_format = TraceFormat::Binary,
+                "--trace-addresses" => {
+                    self.trace_filter.addresses =
+                        Some(parsed(&mut args, &arg, TraceFilter::parse_addresses)?)
+                }
+                "--trace-classes" => {
+                    self.trace_filter.classes =
+                        Some(parsed(&mut args, &arg, TraceFilter::parse_classes)?)
+                }
+                "--trace-cycles" => {
+                    self.trace_filter.cycles =
+                        Some(parsed(&mut args, &arg, TraceFilter::parse_cycles)?)
+                }
+                // read before everything else, see config_path
+                "--config" => {
+                    parsed(&mut args, &arg, |_| Some(()))?;
+                }
+                "-h" | "--help" => self.help = true,
+                _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+                _ => rom_paths.push(arg),
+            }
+        }
+        if !rom_paths.is_empty() {
+            self.rom_paths = rom_paths;
+        }
+        if self.scale == 0 || self.instructions_per_frame == 0 {
+            return Err("--scale and --ipf need to be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    // A single ROM file instead of something to pick from
+    pub fn single_rom(&self) -> bool {
+        match self.rom_paths.as_slice() {
+            [path] => !Path::new(path).is_dir(),
+            _ => false,
+        }
+    }
+}
+
+// The value after an option, turned into what the option needs
+fn parsed<T>(
+    args: &mut impl Iterator<Item = String>,
+    name: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or_else(|| format!("{} needs a value", name))?;
+    parse(&value).ok_or_else(|| format!("bad value `{}` for {}", value, name))
+}
+
+// Profile names are matched ignoring case, like chip8-run does
+pub fn quirks_index(name: &str) -> Option<usize> {
+    QUIRKS_PROFILES
+        .iter()
+        .position(|(profile, _)| profile.eq_ignore_ascii_case(name))
+}
+
+// Two or four RRGGBB colors, the XO-CHIP plane colors stay as they are with two
+pub fn parse_palette(text: &str) -> Option<[[f32; 4]; 4]> {
+    let colors = text
+        .split(',')
+        .map(|color| {
+            let color = color.trim().trim_start_matches('#');
+            // from_str_radix would also take a sign, as in +12345
+            if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let rgb = u32::from_str_radix(color, 16).ok()?;
+            let channel = |shift: u32| ((rgb >> shift) & 0xFF) as f32 / 255.0;
+            Some([channel(16), channel(8), channel(0), 1.0])
+        })
+        .collect::<Option<Vec<[f32; 4]>>>()?;
+    if colors.len() != 2 && colors.len() != 4 {
+        return None;
+    }
+    let mut palette = PALETTE;
+    palette[..colors.len()].copy_from_slice(&colors);
+    Some(palette)
+}
+
+#[cfg(test)]
+#[path = "./options_test.rs"]
+mod test;
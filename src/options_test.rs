@@ -0,0 +1,67 @@
+use super::*;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn parse_test() {
+    let mut options = Options::new();
+    options.parse_args(args(&[])).unwrap();
+    assert_eq!(options, Options::new());
+
+    options
+        .parse_args(args(&[
+            "--scale",
+            "5",
+            "--ipf",
+            "20",
+            "--quirks",
+            "super-chip",
+            "--buzzer",
+            "440",
+            "--mute",
+            "--paused",
+            "--rewind",
+            "30",
+            "--fullscreen",
+            "--seed",
+            "7",
+            "--trace-classes",
+            "8,D",
+            "roms/pong.ch8",
+        ]))
+        .unwrap();
+    assert_eq!(options.scale, 5);
+    assert_eq!(options.instructions_per_frame, 20);
+    assert_eq!(QUIRKS_PROFILES[options.quirks].0, "SUPER-CHIP");
+    assert_eq!(options.buzzer_frequency, 440.0);
+    assert!(options.mute && options.paused && options.fullscreen);
+    assert_eq!(options.rewind_seconds, 30);
+    assert_eq!(options.seed, Some(7));
+    assert_eq!(options.trace_filter.classes, Some(1 << 8 | 1 << 0xD));
+    assert_eq!(options.rom_paths, ["roms/pong.ch8"]);
+
+    for bad in [
+        &["--scale"][..],
+        &["--scale", "0"],
+        &["--quirks", "CHIP-9"],
+        &["--volume", "3"],
+    ] {
+        assert!(Options::new().parse_args(args(bad)).is_err());
+    }
+}
+
+#[test]
+fn parse_palette_test() {
+    let palette = parse_palette("000000,#FF8000").unwrap();
+    assert_eq!(palette[0], [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(palette[1], [1.0, 128.0 / 255.0, 0.0, 1.0]);
+    assert_eq!(palette[2..], PALETTE[2..]);
+    assert!(parse_palette("000000,FFFFFF,FF0000,00FF00").is_some());
+    assert_eq!(parse_palette("000000"), None);
+    assert_eq!(parse_palette("000000,FFFFFF,FF0000"), None);
+    assert_eq!(parse_palette("000000,GGGGGG"), None);
+    assert_eq!(parse_palette("000000,+12345"), None);
+    assert_eq!(parse_palette("-12345,FFFFFF"), None);
+}
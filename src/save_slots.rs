@@ -0,0 +1,57 @@
+use std::fs::{create_dir_all, read, write};
+
+use chip8_core::cartridge_reader::Cartridge;
+use chip8_core::processor::Cpu;
+use piston::input::Key;
+
+const SAVE_DIRECTORY: &str = "saves";
+
+// F1-F10 pick one of the ten save state slots
+pub fn slot_for_key(key: Key) -> Option<usize> {
+    let keys = [
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+    ];
+    keys.iter().position(|slot_key| *slot_key == key)
+}
+
+fn slot_path(cartridge: &Cartridge, slot: usize) -> String {
+    format!(
+        "{}/{}.{}.state",
+        SAVE_DIRECTORY,
+        cartridge.get_game_name(),
+        slot + 1
+    )
+}
+
+pub fn save(cpu: &Cpu, cartridge: &Cartridge, slot: usize) {
+    let path = slot_path(cartridge, slot);
+    let state = cpu.save_state(&cartridge.rom);
+    match create_dir_all(SAVE_DIRECTORY).and_then(|_| write(&path, state)) {
+        Ok(()) => eprintln!("Saved state to {}", path),
+        Err(error) => eprintln!("Failed to save {}: {}", path, error),
+    }
+}
+
+pub fn load(cpu: &mut Cpu, cartridge: &Cartridge, slot: usize) {
+    let path = slot_path(cartridge, slot);
+    let state = match read(&path) {
+        Ok(state) => state,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path, error);
+            return;
+        }
+    };
+    match cpu.load_state(&cartridge.rom, &state) {
+        Ok(()) => eprintln!("Loaded state from {}", path),
+        Err(error) => eprintln!("Failed to load {}: {}", path, error),
+    }
+}